@@ -0,0 +1,92 @@
+// Drawing rules, shared with the off-chain verifier so anyone can re-derive a drawing
+// result from its recorded seed.
+use std::convert::TryFrom;
+
+pub const MAX_DRAWING_NUMBER: u8 = 55;
+// Rules of the drawings done from now on, recorded on their result. Version 0 covers the
+// results drawn before the game types, versions 0 and 1 drew their numbers with
+// `draw_legacy_numbers`
+pub const DRAWING_VERSION: u8 = 2;
+// The first drawings counted their attempts in a u8, the formula keeps their results up to there
+const MAX_SEEDED_ATTEMPTS: u64 = u8::MAX as u64;
+
+pub fn get_random_number_from_seed(seed: u64, max: u64, mut ran_no: u64) -> u64 {
+    let mut seed_value = seed;
+    while seed_value != 0 && seed_value.is_multiple_of(10) {
+        seed_value /= 10;
+    }
+
+    if ran_no == 0 {
+        ran_no = 1;
+    }
+
+    let random_number = (u128::from(seed_value) + u128::from(ran_no)) / (113 + u128::from(ran_no)) * (77 + u128::from(ran_no))
+        % u128::from(max);
+
+    u64::try_from(random_number).unwrap() + 1
+}
+
+/// Draw 6 distinct sorted numbers between 1 and `MAX_DRAWING_NUMBER`. Each number is picked among
+/// those not drawn yet with the next value mixed from the seed, the first one being the value
+/// the lucky ticket is picked with.
+pub fn draw_numbers(seed: u64) -> [u8; 6] {
+    let mut drawed_numbers: [u8; 6] = [0, 0, 0, 0, 0, 0];
+    let mut mixed_seed = seed;
+
+    for i in 0..6 {
+        mixed_seed = mix_seed(mixed_seed);
+        let remaining_numbers: Vec<u8> = (1..=MAX_DRAWING_NUMBER).filter(|number| !drawed_numbers[..i].contains(number)).collect();
+        drawed_numbers[i] = remaining_numbers[(mixed_seed % remaining_numbers.len() as u64) as usize];
+    }
+
+    drawed_numbers.sort_unstable();
+    drawed_numbers
+}
+
+/// Numbers of the results drawn before version 2, kept so they can still be verified.
+pub fn draw_legacy_numbers(seed: u64) -> [u8; 6] {
+    let mut drawed_numbers: [u8; 6] = [0, 0, 0, 0, 0, 0];
+    let mut ran_no: u64 = 1;
+
+    for i in 0..6 {
+        while ran_no < MAX_SEEDED_ATTEMPTS {
+            ran_no += 1;
+            let number = u8::try_from(get_random_number_from_seed(seed, MAX_DRAWING_NUMBER.into(), ran_no)).unwrap();
+            if !drawed_numbers[..i].contains(&number) {
+                drawed_numbers[i] = number;
+                break;
+            }
+        }
+
+        // Small seeds keep giving the same numbers, pick the rest among the numbers not drawn yet
+        if drawed_numbers[i] == 0 {
+            let remaining_numbers: Vec<u8> = (1..=MAX_DRAWING_NUMBER).filter(|number| !drawed_numbers[..i].contains(number)).collect();
+            let index = mix_seed(seed ^ i as u64) % remaining_numbers.len() as u64;
+            drawed_numbers[i] = remaining_numbers[index as usize];
+        }
+    }
+
+    drawed_numbers.sort_unstable();
+    drawed_numbers
+}
+
+/// Index of the winning ticket of a guaranteed-winner jackpot, each ticket is as likely to be picked.
+pub fn pick_lucky_ticket_index(seed: u64, no_of_tickets: u64) -> u64 {
+    mix_seed(seed) % no_of_tickets
+}
+
+// SplitMix64 finalizer, every bit of the seed changes the whole result
+fn mix_seed(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub fn numbers_to_bitmask(numbers: &[u8; 6]) -> u64 {
+    numbers.iter().fold(0, |mask, number| mask | 1 << number)
+}
+
+pub fn count_matches(mask1: u64, mask2: u64) -> u32 {
+    (mask1 & mask2).count_ones()
+}
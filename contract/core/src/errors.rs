@@ -0,0 +1,243 @@
+use std::fmt;
+
+/// Failures of the lottery. The codes are stable and listed in `errors.json`, the messages
+/// may change.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LotteryError {
+    NotInitialized,
+    AlreadyInitialized,
+    InvalidAccountId,
+    NotOwner,
+    NothingToWithdraw,
+    InsufficientBalance,
+    JackpotNotFound,
+    JackpotStillOpen,
+    NoOpenJackpot,
+    JackpotNotSettling,
+    NumberOutOfRange,
+    DuplicatedNumbers,
+    TicketNotFound,
+    NotTicketOwner,
+    TicketNotWon,
+    PrizeAlreadyClaimed,
+    ClaimExpired,
+    UnknownLayout,
+    NoStagedUpgrade,
+    UpgradeCodeMismatch,
+    UpgradeTimelocked,
+    AccountNotRegistered,
+    InsufficientStorageBalance,
+    AccountHasTickets,
+    AccountHasBalance,
+    OneYoctoRequired,
+    MissingRole,
+    NoProposedOwner,
+    NotProposedOwner,
+    ContractPaused,
+    SalesPaused,
+    DrawingPaused,
+    WindingDown,
+    NotWindingDown,
+    NoMultisig,
+    MultisigRequired,
+    InvalidMultisig,
+    NotApprover,
+    ProposalNotFound,
+    ProposalExpired,
+    AlreadyApproved,
+    NotProposer,
+    UnexpectedDeposit,
+    ConfigTooEarly,
+    NoQueuedConfig,
+    InvalidFee,
+    InsufficientTreasury,
+    InvalidTicketPrice,
+    ClaimDurationTooShort,
+    TicketsNotIndexed,
+    JackpotStorageMoving,
+    UpgradeTimelockTooShort,
+    JackpotStillSettling,
+}
+
+impl LotteryError {
+    pub const ALL: [LotteryError; 53] = [
+        LotteryError::NotInitialized,
+        LotteryError::AlreadyInitialized,
+        LotteryError::InvalidAccountId,
+        LotteryError::NotOwner,
+        LotteryError::NothingToWithdraw,
+        LotteryError::InsufficientBalance,
+        LotteryError::JackpotNotFound,
+        LotteryError::JackpotStillOpen,
+        LotteryError::NoOpenJackpot,
+        LotteryError::JackpotNotSettling,
+        LotteryError::NumberOutOfRange,
+        LotteryError::DuplicatedNumbers,
+        LotteryError::TicketNotFound,
+        LotteryError::NotTicketOwner,
+        LotteryError::TicketNotWon,
+        LotteryError::PrizeAlreadyClaimed,
+        LotteryError::ClaimExpired,
+        LotteryError::UnknownLayout,
+        LotteryError::NoStagedUpgrade,
+        LotteryError::UpgradeCodeMismatch,
+        LotteryError::UpgradeTimelocked,
+        LotteryError::AccountNotRegistered,
+        LotteryError::InsufficientStorageBalance,
+        LotteryError::AccountHasTickets,
+        LotteryError::AccountHasBalance,
+        LotteryError::OneYoctoRequired,
+        LotteryError::MissingRole,
+        LotteryError::NoProposedOwner,
+        LotteryError::NotProposedOwner,
+        LotteryError::ContractPaused,
+        LotteryError::SalesPaused,
+        LotteryError::DrawingPaused,
+        LotteryError::WindingDown,
+        LotteryError::NotWindingDown,
+        LotteryError::NoMultisig,
+        LotteryError::MultisigRequired,
+        LotteryError::InvalidMultisig,
+        LotteryError::NotApprover,
+        LotteryError::ProposalNotFound,
+        LotteryError::ProposalExpired,
+        LotteryError::AlreadyApproved,
+        LotteryError::NotProposer,
+        LotteryError::UnexpectedDeposit,
+        LotteryError::ConfigTooEarly,
+        LotteryError::NoQueuedConfig,
+        LotteryError::InvalidFee,
+        LotteryError::InsufficientTreasury,
+        LotteryError::InvalidTicketPrice,
+        LotteryError::ClaimDurationTooShort,
+        LotteryError::TicketsNotIndexed,
+        LotteryError::JackpotStorageMoving,
+        LotteryError::UpgradeTimelockTooShort,
+        LotteryError::JackpotStillSettling,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            LotteryError::NotInitialized => "ERR_NOT_INITIALIZED",
+            LotteryError::AlreadyInitialized => "ERR_ALREADY_INITIALIZED",
+            LotteryError::InvalidAccountId => "ERR_INVALID_ACCOUNT_ID",
+            LotteryError::NotOwner => "ERR_NOT_OWNER",
+            LotteryError::NothingToWithdraw => "ERR_NOTHING_TO_WITHDRAW",
+            LotteryError::InsufficientBalance => "ERR_INSUFFICIENT_BALANCE",
+            LotteryError::JackpotNotFound => "ERR_JACKPOT_NOT_FOUND",
+            LotteryError::JackpotStillOpen => "ERR_JACKPOT_STILL_OPEN",
+            LotteryError::NoOpenJackpot => "ERR_NO_OPEN_JACKPOT",
+            LotteryError::JackpotNotSettling => "ERR_JACKPOT_NOT_SETTLING",
+            LotteryError::NumberOutOfRange => "ERR_NUMBER_OUT_OF_RANGE",
+            LotteryError::DuplicatedNumbers => "ERR_DUPLICATED_NUMBERS",
+            LotteryError::TicketNotFound => "ERR_TICKET_NOT_FOUND",
+            LotteryError::NotTicketOwner => "ERR_NOT_TICKET_OWNER",
+            LotteryError::TicketNotWon => "ERR_TICKET_NOT_WON",
+            LotteryError::PrizeAlreadyClaimed => "ERR_PRIZE_ALREADY_CLAIMED",
+            LotteryError::ClaimExpired => "ERR_CLAIM_EXPIRED",
+            LotteryError::UnknownLayout => "ERR_UNKNOWN_LAYOUT",
+            LotteryError::NoStagedUpgrade => "ERR_NO_STAGED_UPGRADE",
+            LotteryError::UpgradeCodeMismatch => "ERR_UPGRADE_CODE_MISMATCH",
+            LotteryError::UpgradeTimelocked => "ERR_UPGRADE_TIMELOCKED",
+            LotteryError::AccountNotRegistered => "ERR_ACCOUNT_NOT_REGISTERED",
+            LotteryError::InsufficientStorageBalance => "ERR_INSUFFICIENT_STORAGE_BALANCE",
+            LotteryError::AccountHasTickets => "ERR_ACCOUNT_HAS_TICKETS",
+            LotteryError::AccountHasBalance => "ERR_ACCOUNT_HAS_BALANCE",
+            LotteryError::OneYoctoRequired => "ERR_ONE_YOCTO_REQUIRED",
+            LotteryError::MissingRole => "ERR_MISSING_ROLE",
+            LotteryError::NoProposedOwner => "ERR_NO_PROPOSED_OWNER",
+            LotteryError::NotProposedOwner => "ERR_NOT_PROPOSED_OWNER",
+            LotteryError::ContractPaused => "ERR_CONTRACT_PAUSED",
+            LotteryError::SalesPaused => "ERR_SALES_PAUSED",
+            LotteryError::DrawingPaused => "ERR_DRAWING_PAUSED",
+            LotteryError::WindingDown => "ERR_WINDING_DOWN",
+            LotteryError::NotWindingDown => "ERR_NOT_WINDING_DOWN",
+            LotteryError::NoMultisig => "ERR_NO_MULTISIG",
+            LotteryError::MultisigRequired => "ERR_MULTISIG_REQUIRED",
+            LotteryError::InvalidMultisig => "ERR_INVALID_MULTISIG",
+            LotteryError::NotApprover => "ERR_NOT_APPROVER",
+            LotteryError::ProposalNotFound => "ERR_PROPOSAL_NOT_FOUND",
+            LotteryError::ProposalExpired => "ERR_PROPOSAL_EXPIRED",
+            LotteryError::AlreadyApproved => "ERR_ALREADY_APPROVED",
+            LotteryError::NotProposer => "ERR_NOT_PROPOSER",
+            LotteryError::UnexpectedDeposit => "ERR_UNEXPECTED_DEPOSIT",
+            LotteryError::ConfigTooEarly => "ERR_CONFIG_TOO_EARLY",
+            LotteryError::NoQueuedConfig => "ERR_NO_QUEUED_CONFIG",
+            LotteryError::InvalidFee => "ERR_INVALID_FEE",
+            LotteryError::InsufficientTreasury => "ERR_INSUFFICIENT_TREASURY",
+            LotteryError::InvalidTicketPrice => "ERR_INVALID_TICKET_PRICE",
+            LotteryError::ClaimDurationTooShort => "ERR_CLAIM_DURATION_TOO_SHORT",
+            LotteryError::TicketsNotIndexed => "ERR_TICKETS_NOT_INDEXED",
+            LotteryError::JackpotStorageMoving => "ERR_JACKPOT_STORAGE_MOVING",
+            LotteryError::UpgradeTimelockTooShort => "ERR_UPGRADE_TIMELOCK_TOO_SHORT",
+            LotteryError::JackpotStillSettling => "ERR_JACKPOT_STILL_SETTLING",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            LotteryError::NotInitialized => "Should be initialized before usage.",
+            LotteryError::AlreadyInitialized => "Already initialized!",
+            LotteryError::InvalidAccountId => "Invalid owner account!",
+            LotteryError::NotOwner => "The caller must be the contract owner.",
+            LotteryError::NothingToWithdraw => "Nothing to withdraw.",
+            LotteryError::InsufficientBalance => "The balance is not enough to buy a ticket.",
+            LotteryError::JackpotNotFound => "The jackpot does not exist.",
+            LotteryError::JackpotStillOpen => "The latest jackpot is still open. Cannot create a new one!",
+            LotteryError::NoOpenJackpot => "There is no open jackpot.",
+            LotteryError::JackpotNotSettling => "The latest jackpot is not being settled.",
+            LotteryError::NumberOutOfRange => "The chosen number must be between 1 and 55.",
+            LotteryError::DuplicatedNumbers => "The chosen numbers cannot be duplicated.",
+            LotteryError::TicketNotFound => "The ticket does not exist.",
+            LotteryError::NotTicketOwner => "Only the ticket owner can claim its prize.",
+            LotteryError::TicketNotWon => "The ticket has not won.",
+            LotteryError::PrizeAlreadyClaimed => "The prize has already been claimed.",
+            LotteryError::ClaimExpired => "The claim window has expired.",
+            LotteryError::UnknownLayout => "The stored value has an unknown layout.",
+            LotteryError::NoStagedUpgrade => "There is no staged upgrade.",
+            LotteryError::UpgradeCodeMismatch => "The code does not match the staged upgrade.",
+            LotteryError::UpgradeTimelocked => "The upgrade cannot be deployed before its timelock ends.",
+            LotteryError::AccountNotRegistered => "The account is not registered, call storage_deposit first.",
+            LotteryError::InsufficientStorageBalance => "The storage balance is not enough to cover the storage used.",
+            LotteryError::AccountHasTickets => "The account has tickets, its storage cannot be released.",
+            LotteryError::AccountHasBalance => "The account still has a balance, withdraw it or force the unregistration.",
+            LotteryError::OneYoctoRequired => "Attach exactly 1 yoctoNEAR to confirm the call.",
+            LotteryError::MissingRole => "The caller does not have the role required by this method.",
+            LotteryError::NoProposedOwner => "There is no proposed owner.",
+            LotteryError::NotProposedOwner => "Only the proposed owner can accept the ownership.",
+            LotteryError::ContractPaused => "The contract is paused.",
+            LotteryError::SalesPaused => "The ticket sales of the jackpot are paused.",
+            LotteryError::DrawingPaused => "The drawing of the jackpot is paused.",
+            LotteryError::WindingDown => "The lottery is winding down.",
+            LotteryError::NotWindingDown => "The lottery is not winding down.",
+            LotteryError::NoMultisig => "The multisig is not set up.",
+            LotteryError::MultisigRequired => "The action must be approved through a proposal.",
+            LotteryError::InvalidMultisig => "The threshold must be between 1 and the number of distinct approvers.",
+            LotteryError::NotApprover => "The caller is not an approver.",
+            LotteryError::ProposalNotFound => "The proposal does not exist.",
+            LotteryError::ProposalExpired => "The proposal has expired.",
+            LotteryError::AlreadyApproved => "The caller has already approved the proposal.",
+            LotteryError::NotProposer => "Only the proposer can remove the proposal before it expires.",
+            LotteryError::UnexpectedDeposit => "Only a CreateJackpot proposal takes a deposit.",
+            LotteryError::ConfigTooEarly => "The change must take effect at least a day from now.",
+            LotteryError::NoQueuedConfig => "There is no queued change of the parameters.",
+            LotteryError::InvalidFee => "The fee cannot be over 2000 basis points.",
+            LotteryError::InsufficientTreasury => "The treasury balance is not enough.",
+            LotteryError::InvalidTicketPrice => "The ticket price must be over 0.",
+            LotteryError::ClaimDurationTooShort => "The claim duration must be at least 7 days.",
+            LotteryError::TicketsNotIndexed => "The tickets of the jackpot are still being indexed.",
+            LotteryError::JackpotStorageMoving => "The collections of the jackpot are still being moved.",
+            LotteryError::UpgradeTimelockTooShort => "The upgrade timelock must be at least a day.",
+            LotteryError::JackpotStillSettling => "The jackpot is still being settled, its prize is not known yet.",
+        }
+    }
+}
+
+// Panic messages read "ERR_CODE: message", clients match on the code
+impl fmt::Display for LotteryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+impl std::error::Error for LotteryError {}
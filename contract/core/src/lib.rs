@@ -0,0 +1,153 @@
+/*
+ * Chain agnostic rules of the lottery.
+ *
+ * The NEAR contract, the verifier and the simulation tooling share this engine. Everything
+ * that depends on the runtime (block time, randomness, persisted tickets) comes in through
+ * the `Clock`, `Randomness` and `Storage` traits, so the rules can be tested as plain Rust.
+ *
+ */
+pub use crate::drawing::*;
+pub use crate::errors::*;
+pub use crate::rules::*;
+
+mod drawing;
+mod errors;
+mod rules;
+
+pub trait Clock {
+    /// Current time in nanoseconds.
+    fn now(&self) -> u64;
+}
+
+pub trait Randomness {
+    fn seed(&self) -> u64;
+}
+
+/// Read access to the tickets of the jackpot being drawn, in buying order.
+pub trait Storage {
+    fn no_of_tickets(&self) -> u64;
+    fn get_picked_numbers(&self, index: u64) -> [u8; 6];
+}
+
+impl Storage for [[u8; 6]] {
+    fn no_of_tickets(&self) -> u64 {
+        self.len() as u64
+    }
+
+    fn get_picked_numbers(&self, index: u64) -> [u8; 6] {
+        self[index as usize]
+    }
+}
+
+/// Outcome of a drawing, the numbers can be re-derived from the seed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Draw {
+    pub drawed_numbers: [u8; 6],
+    pub drawed_mask: u64,
+    pub created_time: u64,
+    pub seed: u64,
+    pub no_of_tickets: u64,
+    pub lucky_ticket_index: Option<u64>,
+    pub version: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 1_640_995_200_123_456_789;
+
+    struct FixedClock(u64);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> u64 {
+            self.0
+        }
+    }
+
+    impl Randomness for FixedClock {
+        fn seed(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn validate_picked_numbers() {
+        assert_eq!(Ok([1, 2, 3, 4, 5, 55]), validate_numbers(&[55, 4, 3, 2, 5, 1]));
+        assert_eq!(Err(LotteryError::NumberOutOfRange), validate_numbers(&[0, 1, 2, 3, 4, 5]));
+        assert_eq!(Err(LotteryError::NumberOutOfRange), validate_numbers(&[1, 2, 3, 4, 5, 56]));
+        assert_eq!(Err(LotteryError::DuplicatedNumbers), validate_numbers(&[1, 1, 2, 3, 4, 5]));
+    }
+
+    #[test]
+    fn draw_result() {
+        let clock = FixedClock(SEED);
+        let tickets = [[1, 2, 3, 4, 5, 6], [2, 3, 4, 5, 6, 7]];
+
+        let result = draw(&clock, &clock, &tickets[..], false);
+        assert_eq!(draw_numbers(SEED), result.drawed_numbers);
+        assert_eq!(numbers_to_bitmask(&result.drawed_numbers), result.drawed_mask);
+        assert_eq!(2, result.no_of_tickets);
+        assert_eq!(None, result.lucky_ticket_index);
+
+        let result = draw(&clock, &clock, &tickets[..], true);
+        let lucky_ticket_index = result.lucky_ticket_index.unwrap();
+        assert!(is_win_numbers(numbers_to_bitmask(&tickets[lucky_ticket_index as usize]), result.drawed_mask));
+    }
+
+    #[test]
+    fn draw_numbers_from_any_seed() {
+        for seed in [0, 11, 10_000, u64::MAX, SEED].iter() {
+            let numbers = draw_numbers(*seed);
+            assert_eq!(Ok(numbers), validate_numbers(&numbers), "seed {}", seed);
+            let numbers = draw_legacy_numbers(*seed);
+            assert_eq!(Ok(numbers), validate_numbers(&numbers), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn draw_numbers_uniformly() {
+        let mut counts = [0; MAX_DRAWING_NUMBER as usize];
+        for seed in SEED..SEED + 5_500 {
+            for number in draw_numbers(seed).iter() {
+                counts[*number as usize - 1] += 1;
+            }
+        }
+        // 600 draws of each number are expected
+        assert!(counts.iter().all(|count| (480..720).contains(count)), "{:?}", counts);
+    }
+
+    #[test]
+    fn pick_lucky_ticket_uniformly() {
+        let mut counts = [0; 3];
+        for seed in SEED..SEED + 3_000 {
+            counts[pick_lucky_ticket_index(seed, 3) as usize] += 1;
+        }
+        assert!(counts.iter().all(|count| (900..1_100).contains(count)), "{:?}", counts);
+    }
+
+    #[test]
+    fn error_codes_are_documented() {
+        // errors.json is what the clients read, it must list every error with its code
+        let documented = include_str!("../../errors.json");
+        for error in LotteryError::ALL.iter() {
+            let entry = format!("{{ \"code\": \"{}\", \"message\": \"{}\" }}", error.code(), error.message());
+            assert!(documented.contains(&entry), "{} is not documented in errors.json.", error.code());
+        }
+        assert_eq!(LotteryError::ALL.len(), documented.matches("\"code\"").count());
+        assert_eq!("ERR_NO_OPEN_JACKPOT: There is no open jackpot.", LotteryError::NoOpenJackpot.to_string());
+    }
+
+    #[test]
+    fn split_prize_between_winners() {
+        assert_eq!(3, split_prize(10, 3));
+        assert_eq!(0, split_prize(10, 0));
+    }
+
+    #[test]
+    fn take_house_fee() {
+        assert_eq!(50, house_fee(1_000, 500));
+        assert_eq!(0, house_fee(19, 500));
+        assert_eq!(1_000, house_fee(1_000, 10_000));
+    }
+}
@@ -0,0 +1,69 @@
+// Rules of a jackpot which don't depend on where its state is kept.
+use crate::*;
+
+pub const BASIS_POINTS: u128 = 10_000;
+/// The highest fee a jackpot may take, 20% of each ticket price.
+pub const MAX_FEE_BPS: u32 = 2_000;
+
+/// Sort the picked numbers and check they are distinct and between 1 and `MAX_DRAWING_NUMBER`.
+pub fn validate_numbers(picked_numbers: &[u8; 6]) -> Result<[u8; 6], LotteryError> {
+    let mut picked_numbers = *picked_numbers;
+    picked_numbers.sort_unstable();
+
+    for i in 0..6 {
+        if picked_numbers[i] < 1 || picked_numbers[i] > MAX_DRAWING_NUMBER {
+            return Err(LotteryError::NumberOutOfRange);
+        }
+
+        if i > 0 && picked_numbers[i - 1] == picked_numbers[i] {
+            return Err(LotteryError::DuplicatedNumbers);
+        }
+    }
+
+    Ok(picked_numbers)
+}
+
+pub fn is_win_numbers(picked_mask: u64, drawed_mask: u64) -> bool {
+    count_matches(picked_mask, drawed_mask) == 6
+}
+
+/// Prize of each winner, the remainder stays locked in the jackpot.
+pub fn split_prize(locked_amount: u128, no_of_winners: u64) -> u128 {
+    if no_of_winners == 0 {
+        return 0;
+    }
+
+    locked_amount / no_of_winners as u128
+}
+
+/// Share of `amount` taken as the house fee, rounded down.
+pub fn house_fee(amount: u128, fee_bps: u32) -> u128 {
+    amount * fee_bps as u128 / BASIS_POINTS
+}
+
+/// Draw a result over the tickets of the storage. For a guaranteed-winner jackpot the numbers of a
+/// ticket picked from the seed are used, so there is at least one winner.
+pub fn draw<C: Clock, R: Randomness, S: Storage + ?Sized>(clock: &C, randomness: &R, storage: &S, guaranteed_winner: bool) -> Draw {
+    let seed = randomness.seed();
+    let no_of_tickets = storage.no_of_tickets();
+
+    let mut lucky_ticket_index = None;
+    let drawed_numbers = if guaranteed_winner && no_of_tickets > 0 {
+        let index = pick_lucky_ticket_index(seed, no_of_tickets);
+        lucky_ticket_index = Some(index);
+        storage.get_picked_numbers(index)
+    }
+    else {
+        draw_numbers(seed)
+    };
+
+    Draw {
+        drawed_numbers,
+        drawed_mask: numbers_to_bitmask(&drawed_numbers),
+        created_time: clock.now(),
+        seed,
+        no_of_tickets,
+        lucky_ticket_index,
+        version: DRAWING_VERSION,
+    }
+}
@@ -0,0 +1,404 @@
+use std::fmt;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+
+use crate::events::*;
+
+// Amounts are yoctoNEAR which don't fit into SQLite integers, so they are kept as decimal text
+// and the arithmetic is done here.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS accounts (
+        account_id TEXT PRIMARY KEY,
+        balance TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS jackpots (
+        id INTEGER PRIMARY KEY,
+        ticket_price TEXT NOT NULL,
+        locked_amount TEXT NOT NULL,
+        prize_amount TEXT NOT NULL,
+        claim_duration INTEGER NOT NULL,
+        end_time INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS tickets (
+        id INTEGER PRIMARY KEY,
+        jackpot_id INTEGER NOT NULL,
+        account_id TEXT NOT NULL,
+        picked_numbers TEXT NOT NULL,
+        price TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS tickets_account_id ON tickets (account_id);
+    CREATE TABLE IF NOT EXISTS drawed_results (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        jackpot_id INTEGER NOT NULL,
+        drawed_numbers TEXT NOT NULL,
+        created_time INTEGER NOT NULL,
+        game_type TEXT
+    );
+    CREATE TABLE IF NOT EXISTS winners (
+        ticket_id INTEGER PRIMARY KEY,
+        jackpot_id INTEGER NOT NULL,
+        account_id TEXT NOT NULL,
+        prize_amount TEXT NOT NULL,
+        paid_amount TEXT
+    );
+    CREATE TABLE IF NOT EXISTS applied_events (
+        receipt_id TEXT NOT NULL,
+        log_index INTEGER NOT NULL,
+        data_index INTEGER NOT NULL,
+        PRIMARY KEY (receipt_id, log_index, data_index)
+    );
+";
+
+#[derive(Debug)]
+pub enum IndexError {
+    Sqlite(rusqlite::Error),
+    // An event which only moves amounts was read without the receipt which logged it
+    MissingReceiptId,
+    // An amount would go below zero, some earlier events were not read
+    Underflow,
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IndexError::Sqlite(e) => write!(f, "{}", e),
+            IndexError::MissingReceiptId => write!(f, "Deposits, withdrawals, refunds and transfers must be read from a receipt outcome with its receipt_id."),
+            IndexError::Underflow => write!(f, "An amount would go below zero, some earlier events were not read."),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for IndexError {
+    fn from(e: rusqlite::Error) -> Self {
+        IndexError::Sqlite(e)
+    }
+}
+
+pub struct Database {
+    connection: Connection,
+}
+
+impl Database {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Apply the events of one input, all or nothing. The events already applied from the same
+    /// receipt are skipped.
+    pub fn apply_events(&mut self, events: &[KeyedEvent]) -> Result<(), IndexError> {
+        let transaction = self.connection.transaction()?;
+        for keyed_event in events {
+            match &keyed_event.receipt_id {
+                Some(receipt_id) => {
+                    let inserted = transaction.execute(
+                        "INSERT OR IGNORE INTO applied_events (receipt_id, log_index, data_index) VALUES (?1, ?2, ?3)",
+                        params![receipt_id, keyed_event.log_index, keyed_event.data_index],
+                    )?;
+                    if inserted == 0 {
+                        continue;
+                    }
+                },
+                None if !keyed_event.event.has_natural_key() => return Err(IndexError::MissingReceiptId),
+                None => {},
+            }
+            apply_event(&transaction, &keyed_event.event)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn get_owner_id(&self) -> rusqlite::Result<Option<String>> {
+        self.connection
+            .query_row("SELECT value FROM meta WHERE key = 'owner_id'", [], |row| row.get(0))
+            .optional()
+    }
+
+    pub fn get_account_balance(&self, account_id: &str) -> rusqlite::Result<u128> {
+        get_amount(&self.connection, "SELECT balance FROM accounts WHERE account_id = ?1", params![account_id])
+    }
+
+    /// Fees collected and not withdrawn by the owner yet.
+    pub fn get_treasury(&self) -> rusqlite::Result<u128> {
+        get_amount(&self.connection, "SELECT value FROM meta WHERE key = 'treasury'", params![])
+    }
+
+    pub fn get_account_tickets(&self, account_id: &str) -> rusqlite::Result<Vec<Value>> {
+        let mut statement = self.connection.prepare(
+            "SELECT t.id, t.jackpot_id, t.picked_numbers, t.price, w.prize_amount, w.paid_amount
+             FROM tickets t LEFT JOIN winners w ON w.ticket_id = t.id
+             WHERE t.account_id = ?1 ORDER BY t.id",
+        )?;
+        let rows = statement.query_map(params![account_id], |row| {
+            Ok(json!({
+                "id": row.get::<_, i64>(0)?,
+                "jackpotId": row.get::<_, i64>(1)?,
+                "pickedNumbers": parse_numbers(&row.get::<_, String>(2)?),
+                "price": row.get::<_, String>(3)?,
+                "prizeAmount": row.get::<_, Option<String>>(4)?,
+                "paidAmount": row.get::<_, Option<String>>(5)?,
+            }))
+        })?;
+        rows.collect()
+    }
+
+    pub fn get_jackpots(&self) -> rusqlite::Result<Vec<Value>> {
+        let mut statement = self.connection.prepare(
+            "SELECT j.id, j.ticket_price, j.locked_amount, j.prize_amount, j.end_time,
+                (SELECT COUNT(*) FROM tickets t WHERE t.jackpot_id = j.id),
+                (SELECT COUNT(*) FROM winners w WHERE w.jackpot_id = j.id)
+             FROM jackpots j ORDER BY j.id",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok(json!({
+                "id": row.get::<_, i64>(0)?,
+                "ticketPrice": row.get::<_, String>(1)?,
+                "lockedAmount": row.get::<_, String>(2)?,
+                "prizeAmount": row.get::<_, String>(3)?,
+                "endTime": row.get::<_, Option<i64>>(4)?,
+                "noOfTickets": row.get::<_, i64>(5)?,
+                "noOfWinTickets": row.get::<_, i64>(6)?,
+            }))
+        })?;
+        rows.collect()
+    }
+
+    /// Total prize amount claimed by winners, per jackpot.
+    pub fn get_jackpot_payouts(&self) -> rusqlite::Result<Vec<(u32, u128)>> {
+        let mut statement = self.connection.prepare(
+            "SELECT j.id, w.paid_amount FROM jackpots j LEFT JOIN winners w ON w.jackpot_id = j.id ORDER BY j.id",
+        )?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, Option<String>>(1)?)))?;
+
+        let mut payouts: Vec<(u32, u128)> = Vec::new();
+        for row in rows {
+            let (jackpot_id, paid_amount) = row?;
+            let paid_amount = paid_amount.map(|amount| parse_amount(&amount)).unwrap_or(0);
+            match payouts.last_mut() {
+                Some((id, total)) if *id == jackpot_id => *total += paid_amount,
+                _ => payouts.push((jackpot_id, paid_amount)),
+            }
+        }
+
+        Ok(payouts)
+    }
+
+    pub fn get_drawed_results(&self, jackpot_id: u32) -> rusqlite::Result<Vec<Value>> {
+        let mut statement = self.connection.prepare(
+            "SELECT drawed_numbers, created_time, game_type FROM drawed_results WHERE jackpot_id = ?1 ORDER BY id",
+        )?;
+        let rows = statement.query_map(params![jackpot_id], |row| {
+            Ok(json!({
+                "drawedNumbers": parse_numbers(&row.get::<_, String>(0)?),
+                "createdTime": row.get::<_, i64>(1)?,
+                "gameType": row.get::<_, Option<String>>(2)?,
+            }))
+        })?;
+        rows.collect()
+    }
+
+    pub fn get_winners(&self, jackpot_id: Option<u32>) -> rusqlite::Result<Vec<Value>> {
+        let mut statement = self.connection.prepare(
+            "SELECT ticket_id, jackpot_id, account_id, prize_amount, paid_amount FROM winners
+             WHERE ?1 IS NULL OR jackpot_id = ?1 ORDER BY jackpot_id, ticket_id",
+        )?;
+        let rows = statement.query_map(params![jackpot_id], |row| {
+            Ok(json!({
+                "ticketId": row.get::<_, i64>(0)?,
+                "jackpotId": row.get::<_, i64>(1)?,
+                "accountId": row.get::<_, String>(2)?,
+                "prizeAmount": row.get::<_, String>(3)?,
+                "paidAmount": row.get::<_, Option<String>>(4)?,
+            }))
+        })?;
+        rows.collect()
+    }
+}
+
+// An input read twice must not count its tickets, drawings and winners twice, so the events of
+// the rows with a key are skipped with their amounts when the row is already there. The other
+// events are skipped by their receipt in `apply_events`.
+fn apply_event(connection: &Connection, event: &LotteryEvent) -> Result<(), IndexError> {
+    match event {
+        LotteryEvent::Deposit(e) => {
+            add_account_balance(connection, &e.account_id, e.amount as i128)?;
+        },
+        LotteryEvent::Withdraw(e) => {
+            add_account_balance(connection, &e.account_id, -(e.amount as i128))?;
+        },
+        LotteryEvent::JackpotCreated(e) => {
+            connection.execute(
+                "INSERT OR IGNORE INTO jackpots (id, ticket_price, locked_amount, prize_amount, claim_duration) VALUES (?1, ?2, ?3, '0', ?4)",
+                params![e.jackpot_id, e.ticket_price.to_string(), e.initialized_amount.to_string(), e.claim_duration as i64],
+            )?;
+        },
+        LotteryEvent::TicketBought(e) => {
+            let inserted = connection.execute(
+                "INSERT OR IGNORE INTO tickets (id, jackpot_id, account_id, picked_numbers, price) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![e.ticket_id as i64, e.jackpot_id, e.account_id, format_numbers(&e.picked_numbers), e.amount.to_string()],
+            )?;
+            if inserted == 0 {
+                return Ok(());
+            }
+            add_account_balance(connection, &e.account_id, -(e.amount as i128))?;
+            add_locked_amount(connection, e.jackpot_id, (e.amount - e.fee) as i128)?;
+            add_treasury(connection, e.fee as i128)?;
+        },
+        LotteryEvent::DrawResult(e) => {
+            // A jackpot is drawn at most once per block
+            connection.execute(
+                "INSERT INTO drawed_results (jackpot_id, drawed_numbers, created_time, game_type)
+                 SELECT ?1, ?2, ?3, ?4
+                 WHERE NOT EXISTS (SELECT 1 FROM drawed_results WHERE jackpot_id = ?1 AND created_time = ?3)",
+                params![e.jackpot_id, format_numbers(&e.drawed_numbers), e.created_time as i64, e.game_type],
+            )?;
+        },
+        LotteryEvent::WinTicketsFound(e) => {
+            insert_winners(connection, &e.ticket_ids)?;
+        },
+        LotteryEvent::JackpotSettled(e) => {
+            connection.execute(
+                "UPDATE jackpots SET prize_amount = ?2, end_time = ?3 WHERE id = ?1",
+                params![e.jackpot_id, e.prize_amount.to_string(), e.end_time.map(|t| t as i64)],
+            )?;
+            // The prize is only known once the last batch is settled
+            insert_winners(connection, &e.win_ticket_ids)?;
+            connection.execute(
+                "UPDATE winners SET prize_amount = ?2 WHERE jackpot_id = ?1",
+                params![e.jackpot_id, e.prize_amount.to_string()],
+            )?;
+        },
+        LotteryEvent::WinnerPaid(e) => {
+            let updated = connection.execute(
+                "UPDATE winners SET paid_amount = ?2 WHERE ticket_id = ?1 AND paid_amount IS NULL",
+                params![e.ticket_id as i64, e.amount.to_string()],
+            )?;
+            if updated == 0 {
+                return Ok(());
+            }
+            add_account_balance(connection, &e.account_id, e.amount as i128)?;
+            add_locked_amount(connection, e.jackpot_id, -(e.amount as i128))?;
+        },
+        LotteryEvent::PrizeRolledOver(e) => {
+            add_locked_amount(connection, e.from_jackpot_id, -(e.amount as i128))?;
+            add_locked_amount(connection, e.to_jackpot_id, e.amount as i128)?;
+        },
+        LotteryEvent::OwnerChanged(e) => {
+            connection.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES ('owner_id', ?1)",
+                params![e.new_owner_id],
+            )?;
+        },
+        LotteryEvent::WindDownStarted(e) => {
+            if let Some(jackpot_id) = e.refund_jackpot_id {
+                connection.execute(
+                    "UPDATE jackpots SET end_time = ?2 WHERE id = ?1",
+                    params![jackpot_id, e.started_time as i64],
+                )?;
+                // The winners found by an unfinished settlement are dropped, every ticket is refunded
+                connection.execute("DELETE FROM winners WHERE jackpot_id = ?1", params![jackpot_id])?;
+            }
+        },
+        LotteryEvent::TicketsRefunded(e) => {
+            for ticket_id in &e.ticket_ids {
+                let account_id: String = connection.query_row(
+                    "SELECT account_id FROM tickets WHERE id = ?1",
+                    params![*ticket_id as i64],
+                    |row| row.get(0),
+                )?;
+                add_account_balance(connection, &account_id, e.amount as i128)?;
+            }
+            let no_of_tickets = e.ticket_ids.len() as u128;
+            add_locked_amount(connection, e.jackpot_id, -(((e.amount - e.fee) * no_of_tickets) as i128))?;
+            add_treasury(connection, -((e.fee * no_of_tickets) as i128))?;
+        },
+        LotteryEvent::JackpotRemainderReleased(e) => {
+            add_locked_amount(connection, e.jackpot_id, -(e.amount as i128))?;
+            add_treasury(connection, e.amount as i128)?;
+        },
+        LotteryEvent::TreasuryWithdrawn(e) => {
+            add_treasury(connection, -(e.amount as i128))?;
+        },
+    }
+
+    Ok(())
+}
+
+// Their prize is set once the jackpot is settled
+fn insert_winners(connection: &Connection, ticket_ids: &[u64]) -> rusqlite::Result<()> {
+    for ticket_id in ticket_ids {
+        connection.execute(
+            "INSERT OR IGNORE INTO winners (ticket_id, jackpot_id, account_id, prize_amount)
+             SELECT id, jackpot_id, account_id, '0' FROM tickets WHERE id = ?1",
+            params![*ticket_id as i64],
+        )?;
+    }
+    Ok(())
+}
+
+fn add_account_balance(connection: &Connection, account_id: &str, amount: i128) -> Result<(), IndexError> {
+    let balance = get_amount(connection, "SELECT balance FROM accounts WHERE account_id = ?1", params![account_id])?;
+    connection.execute(
+        "INSERT OR REPLACE INTO accounts (account_id, balance) VALUES (?1, ?2)",
+        params![account_id, add_amount(balance, amount)?.to_string()],
+    )?;
+    Ok(())
+}
+
+fn add_locked_amount(connection: &Connection, jackpot_id: u32, amount: i128) -> Result<(), IndexError> {
+    let locked_amount = get_amount(connection, "SELECT locked_amount FROM jackpots WHERE id = ?1", params![jackpot_id])?;
+    connection.execute(
+        "UPDATE jackpots SET locked_amount = ?2 WHERE id = ?1",
+        params![jackpot_id, add_amount(locked_amount, amount)?.to_string()],
+    )?;
+    Ok(())
+}
+
+fn add_treasury(connection: &Connection, amount: i128) -> Result<(), IndexError> {
+    let treasury = get_amount(connection, "SELECT value FROM meta WHERE key = 'treasury'", params![])?;
+    connection.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES ('treasury', ?1)",
+        params![add_amount(treasury, amount)?.to_string()],
+    )?;
+    Ok(())
+}
+
+fn get_amount(connection: &Connection, sql: &str, params: &[&dyn rusqlite::ToSql]) -> rusqlite::Result<u128> {
+    let amount: Option<String> = connection.query_row(sql, params, |row| row.get(0)).optional()?;
+    Ok(amount.map(|amount| parse_amount(&amount)).unwrap_or(0))
+}
+
+fn add_amount(amount: u128, delta: i128) -> Result<u128, IndexError> {
+    if delta < 0 {
+        amount.checked_sub(delta.unsigned_abs()).ok_or(IndexError::Underflow)
+    }
+    else {
+        Ok(amount + delta as u128)
+    }
+}
+
+fn parse_amount(amount: &str) -> u128 {
+    amount.parse().unwrap_or(0)
+}
+
+fn format_numbers(numbers: &[u8; 6]) -> String {
+    numbers.iter().map(|n| n.to_string()).collect::<Vec<String>>().join(",")
+}
+
+fn parse_numbers(numbers: &str) -> Vec<u8> {
+    numbers.split(',').filter_map(|n| n.parse().ok()).collect()
+}
@@ -0,0 +1,402 @@
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+use serde_json::Value;
+
+const EVENT_LOG_PREFIX: &str = "EVENT_JSON:";
+const EVENT_STANDARD: &str = "lottery";
+
+// Envelope of a NEP-297 event log
+#[derive(Deserialize)]
+struct EventLog {
+    standard: String,
+    version: String,
+    event: String,
+    data: Vec<Value>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct Deposit {
+    pub account_id: String,
+    #[serde(deserialize_with = "from_u128_string")]
+    pub amount: u128,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct Withdraw {
+    pub account_id: String,
+    #[serde(deserialize_with = "from_u128_string")]
+    pub amount: u128,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct JackpotCreated {
+    pub jackpot_id: u32,
+    #[serde(deserialize_with = "from_u128_string")]
+    pub ticket_price: u128,
+    #[serde(deserialize_with = "from_u128_string")]
+    pub initialized_amount: u128,
+    #[serde(deserialize_with = "from_u64_string")]
+    pub claim_duration: u64,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct TicketBought {
+    pub jackpot_id: u32,
+    pub ticket_id: u64,
+    pub account_id: String,
+    pub picked_numbers: [u8; 6],
+    #[serde(deserialize_with = "from_u128_string")]
+    pub amount: u128,
+    // Part of the amount paid into the treasury
+    #[serde(deserialize_with = "from_u128_string")]
+    pub fee: u128,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct DrawResult {
+    pub jackpot_id: u32,
+    pub drawed_numbers: [u8; 6],
+    #[serde(deserialize_with = "from_u64_string")]
+    pub created_time: u64,
+    pub game_type: String,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct WinTicketsFound {
+    pub jackpot_id: u32,
+    pub ticket_ids: Vec<u64>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct JackpotSettled {
+    pub jackpot_id: u32,
+    // Listed by the events of version 1, the later ones log the winners per settle batch
+    #[serde(skip)]
+    pub win_ticket_ids: Vec<u64>,
+    #[serde(deserialize_with = "from_u128_string")]
+    pub prize_amount: u128,
+    #[serde(deserialize_with = "from_optional_u64_string")]
+    pub end_time: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct WinnerPaid {
+    pub jackpot_id: u32,
+    pub ticket_id: u64,
+    pub account_id: String,
+    #[serde(deserialize_with = "from_u128_string")]
+    pub amount: u128,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct PrizeRolledOver {
+    pub from_jackpot_id: u32,
+    pub to_jackpot_id: u32,
+    #[serde(deserialize_with = "from_u128_string")]
+    pub amount: u128,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct WindDownStarted {
+    pub refund_jackpot_id: Option<u32>,
+    #[serde(deserialize_with = "from_u64_string")]
+    pub started_time: u64,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct TicketsRefunded {
+    pub jackpot_id: u32,
+    pub ticket_ids: Vec<u64>,
+    // Refunded for each ticket
+    #[serde(deserialize_with = "from_u128_string")]
+    pub amount: u128,
+    // Part of the amount taken back from the treasury
+    #[serde(deserialize_with = "from_u128_string")]
+    pub fee: u128,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct JackpotRemainderReleased {
+    pub jackpot_id: u32,
+    #[serde(deserialize_with = "from_u128_string")]
+    pub amount: u128,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct TreasuryWithdrawn {
+    pub account_id: String,
+    #[serde(deserialize_with = "from_u128_string")]
+    pub amount: u128,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct OwnerChanged {
+    pub old_owner_id: String,
+    pub new_owner_id: String,
+}
+
+// Events of version 1 whose data changed in version 2. The tickets were bought and refunded
+// without fee, a drawing forced a winner instead of having a game type and the settlement listed
+// the winners.
+#[derive(Deserialize)]
+struct TicketBoughtV1 {
+    jackpot_id: u32,
+    ticket_id: u64,
+    account_id: String,
+    picked_numbers: [u8; 6],
+    #[serde(deserialize_with = "from_u128_string")]
+    amount: u128,
+}
+
+impl From<TicketBoughtV1> for TicketBought {
+    fn from(e: TicketBoughtV1) -> Self {
+        Self {
+            jackpot_id: e.jackpot_id,
+            ticket_id: e.ticket_id,
+            account_id: e.account_id,
+            picked_numbers: e.picked_numbers,
+            amount: e.amount,
+            fee: 0,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DrawResultV1 {
+    jackpot_id: u32,
+    drawed_numbers: [u8; 6],
+    #[serde(deserialize_with = "from_u64_string")]
+    created_time: u64,
+    force_win: bool,
+}
+
+impl From<DrawResultV1> for DrawResult {
+    fn from(e: DrawResultV1) -> Self {
+        Self {
+            jackpot_id: e.jackpot_id,
+            drawed_numbers: e.drawed_numbers,
+            created_time: e.created_time,
+            game_type: String::from(if e.force_win { "GuaranteedWinner" } else { "Standard" }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct JackpotSettledV1 {
+    jackpot_id: u32,
+    win_ticket_ids: Vec<u64>,
+    #[serde(deserialize_with = "from_u128_string")]
+    prize_amount: u128,
+    #[serde(deserialize_with = "from_optional_u64_string")]
+    end_time: Option<u64>,
+}
+
+impl From<JackpotSettledV1> for JackpotSettled {
+    fn from(e: JackpotSettledV1) -> Self {
+        Self {
+            jackpot_id: e.jackpot_id,
+            win_ticket_ids: e.win_ticket_ids,
+            prize_amount: e.prize_amount,
+            end_time: e.end_time,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TicketsRefundedV1 {
+    jackpot_id: u32,
+    ticket_ids: Vec<u64>,
+    #[serde(deserialize_with = "from_u128_string")]
+    amount: u128,
+}
+
+impl From<TicketsRefundedV1> for TicketsRefunded {
+    fn from(e: TicketsRefundedV1) -> Self {
+        Self {
+            jackpot_id: e.jackpot_id,
+            ticket_ids: e.ticket_ids,
+            amount: e.amount,
+            fee: 0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LotteryEvent {
+    Deposit(Deposit),
+    Withdraw(Withdraw),
+    JackpotCreated(JackpotCreated),
+    TicketBought(TicketBought),
+    DrawResult(DrawResult),
+    WinTicketsFound(WinTicketsFound),
+    JackpotSettled(JackpotSettled),
+    WinnerPaid(WinnerPaid),
+    PrizeRolledOver(PrizeRolledOver),
+    OwnerChanged(OwnerChanged),
+    WindDownStarted(WindDownStarted),
+    TicketsRefunded(TicketsRefunded),
+    JackpotRemainderReleased(JackpotRemainderReleased),
+    TreasuryWithdrawn(TreasuryWithdrawn),
+}
+
+impl LotteryEvent {
+    /// Whether the same event read again is told apart by its content, e.g. the id of a bought
+    /// ticket. The others only move amounts and are told apart by the receipt which logged them.
+    pub fn has_natural_key(&self) -> bool {
+        matches!(
+            self,
+            LotteryEvent::JackpotCreated(_)
+                | LotteryEvent::TicketBought(_)
+                | LotteryEvent::DrawResult(_)
+                | LotteryEvent::WinTicketsFound(_)
+                | LotteryEvent::JackpotSettled(_)
+                | LotteryEvent::WinnerPaid(_)
+                | LotteryEvent::OwnerChanged(_)
+                | LotteryEvent::WindDownStarted(_)
+        )
+    }
+}
+
+// An event and the place it was logged at
+#[derive(Debug, PartialEq)]
+pub struct KeyedEvent {
+    // Receipt which logged the event, unknown for a bare log
+    pub receipt_id: Option<String>,
+    // Position of the log in the receipt outcome
+    pub log_index: u32,
+    // Position of the event in the data of the log
+    pub data_index: u32,
+    pub event: LotteryEvent,
+}
+
+/// Parse one line of input into the lottery events it holds.
+///
+/// A line is either a raw `EVENT_JSON:` log, a JSON string holding such a log, the event
+/// object itself, or a receipt outcome with a `logs` array and its `receipt_id`.
+pub fn parse_line(line: &str) -> Result<Vec<KeyedEvent>, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(vec![]);
+    }
+
+    if let Some(log) = line.strip_prefix(EVENT_LOG_PREFIX) {
+        return parse_event_log(log, &None, 0);
+    }
+
+    let value: Value = serde_json::from_str(line).map_err(|e| format!("Invalid JSON line: {}", e))?;
+    match value {
+        Value::Object(ref object) if object.contains_key("logs") => {
+            let receipt_id = object.get("receipt_id").and_then(Value::as_str).map(String::from);
+            let mut events = Vec::new();
+            for (log_index, log) in object["logs"].as_array().cloned().unwrap_or_default().into_iter().enumerate() {
+                events.extend(parse_value(log, &receipt_id, log_index as u32)?);
+            }
+            Ok(events)
+        },
+        _ => parse_value(value, &None, 0),
+    }
+}
+
+fn parse_value(value: Value, receipt_id: &Option<String>, log_index: u32) -> Result<Vec<KeyedEvent>, String> {
+    match value {
+        Value::String(log) => match log.strip_prefix(EVENT_LOG_PREFIX) {
+            Some(log) => parse_event_log(log, receipt_id, log_index),
+            // Plain logs such as the init message are not events
+            None => Ok(vec![]),
+        },
+        Value::Object(_) => {
+            let event_log = serde_json::from_value(value).map_err(|e| format!("Invalid event: {}", e))?;
+            parse_event(event_log, receipt_id, log_index)
+        },
+        _ => Err(String::from("Unsupported line, expected an event log or object.")),
+    }
+}
+
+fn parse_event_log(log: &str, receipt_id: &Option<String>, log_index: u32) -> Result<Vec<KeyedEvent>, String> {
+    parse_event(serde_json::from_str(log).map_err(|e| format!("Invalid event: {}", e))?, receipt_id, log_index)
+}
+
+fn parse_event(event_log: EventLog, receipt_id: &Option<String>, log_index: u32) -> Result<Vec<KeyedEvent>, String> {
+    // Events of other standards (e.g. NEP-141 transfers in the same receipt) are skipped
+    if event_log.standard != EVENT_STANDARD {
+        return Ok(vec![]);
+    }
+
+    // The data of an event only changes with the major version
+    let parse_data = match event_log.version.split('.').next() {
+        Some("1") => parse_event_data_v1,
+        Some("2") => parse_event_data,
+        _ => return Err(format!("Unsupported event version {}.", event_log.version)),
+    };
+
+    let mut events = Vec::new();
+    for (data_index, data) in event_log.data.into_iter().enumerate() {
+        if let Some(event) = parse_data(&event_log.event, data)? {
+            events.push(KeyedEvent { receipt_id: receipt_id.clone(), log_index, data_index: data_index as u32, event });
+        }
+    }
+
+    Ok(events)
+}
+
+fn parse_event_data(name: &str, data: Value) -> Result<Option<LotteryEvent>, String> {
+    let event = match name {
+        "deposit" => serde_json::from_value(data).map(LotteryEvent::Deposit),
+        "withdraw" => serde_json::from_value(data).map(LotteryEvent::Withdraw),
+        "jackpot_created" => serde_json::from_value(data).map(LotteryEvent::JackpotCreated),
+        "ticket_bought" => serde_json::from_value(data).map(LotteryEvent::TicketBought),
+        "draw_result" => serde_json::from_value(data).map(LotteryEvent::DrawResult),
+        "win_tickets_found" => serde_json::from_value(data).map(LotteryEvent::WinTicketsFound),
+        "jackpot_settled" => serde_json::from_value(data).map(LotteryEvent::JackpotSettled),
+        "winner_paid" => serde_json::from_value(data).map(LotteryEvent::WinnerPaid),
+        "prize_rolled_over" => serde_json::from_value(data).map(LotteryEvent::PrizeRolledOver),
+        "owner_changed" => serde_json::from_value(data).map(LotteryEvent::OwnerChanged),
+        "wind_down_started" => serde_json::from_value(data).map(LotteryEvent::WindDownStarted),
+        "tickets_refunded" => serde_json::from_value(data).map(LotteryEvent::TicketsRefunded),
+        "jackpot_remainder_released" => serde_json::from_value(data).map(LotteryEvent::JackpotRemainderReleased),
+        "treasury_withdrawn" => serde_json::from_value(data).map(LotteryEvent::TreasuryWithdrawn),
+        // Events which don't change the indexed state
+        _ => return Ok(None),
+    };
+
+    event.map(Some).map_err(|e| format!("Invalid {} event: {}", name, e))
+}
+
+fn parse_event_data_v1(name: &str, data: Value) -> Result<Option<LotteryEvent>, String> {
+    let event = match name {
+        "ticket_bought" => serde_json::from_value::<TicketBoughtV1>(data).map(|e| LotteryEvent::TicketBought(e.into())),
+        "draw_result" => serde_json::from_value::<DrawResultV1>(data).map(|e| LotteryEvent::DrawResult(e.into())),
+        "jackpot_settled" => serde_json::from_value::<JackpotSettledV1>(data).map(|e| LotteryEvent::JackpotSettled(e.into())),
+        "tickets_refunded" => serde_json::from_value::<TicketsRefundedV1>(data).map(|e| LotteryEvent::TicketsRefunded(e.into())),
+        // The other events are the same in both versions
+        _ => return parse_event_data(name, data),
+    };
+
+    event.map(Some).map_err(|e| format!("Invalid {} event: {}", name, e))
+}
+
+fn from_u128_string<'de, D>(deserializer: D) -> Result<u128, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(de::Error::custom)
+}
+
+fn from_u64_string<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(de::Error::custom)
+}
+
+fn from_optional_u64_string<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(value) => value.parse().map(Some).map_err(de::Error::custom),
+    }
+}
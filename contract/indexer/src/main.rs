@@ -0,0 +1,281 @@
+/*
+ * Off-chain indexer for the lottery contract.
+ *
+ * It reads the NEP-297 `EVENT_JSON:` logs emitted by the contract, one JSON line each, and
+ * rebuilds the jackpots, tickets, account balances and winners into a SQLite database which
+ * can be queried without calling the contract views. The events are keyed by the receipt which
+ * logged them, so reading a receipt outcome again doesn't apply its events twice.
+ *
+ * Usage:
+ *   lottery-indexer <db> ingest [FILE]       Read event lines from FILE, or stdin when omitted
+ *   lottery-indexer <db> tickets <ACCOUNT>   All tickets bought by an account
+ *   lottery-indexer <db> balance <ACCOUNT>   Balance of an account inside the contract
+ *   lottery-indexer <db> owner               Current owner of the contract
+ *   lottery-indexer <db> treasury            Fees collected and not withdrawn yet
+ *   lottery-indexer <db> jackpots            All jackpots
+ *   lottery-indexer <db> payouts             Total prize paid per jackpot
+ *   lottery-indexer <db> winners [JACKPOT]   Winner history, optionally for a single jackpot
+ *   lottery-indexer <db> draws <JACKPOT>     Drawing results of a jackpot
+ *
+ */
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::process;
+
+use serde_json::json;
+
+use crate::db::Database;
+use crate::events::parse_line;
+
+mod db;
+mod events;
+
+const USAGE: &str = "Usage: lottery-indexer <db> <ingest [FILE] | tickets ACCOUNT | balance ACCOUNT | owner | treasury | jackpots | payouts | winners [JACKPOT] | draws JACKPOT>";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    if args.len() < 2 {
+        return Err(String::from(USAGE));
+    }
+
+    let mut db = Database::open(&args[0]).map_err(|e| e.to_string())?;
+    let argument = args.get(2).map(String::as_str);
+
+    match (args[1].as_str(), argument) {
+        ("ingest", None) | ("ingest", Some("-")) => {
+            let stdin = io::stdin();
+            let count = ingest(&mut db, stdin.lock())?;
+            eprintln!("Indexed {} events.", count);
+        },
+        ("ingest", Some(path)) => {
+            let file = File::open(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+            let count = ingest(&mut db, BufReader::new(file))?;
+            eprintln!("Indexed {} events.", count);
+        },
+        ("tickets", Some(account_id)) => {
+            for ticket in db.get_account_tickets(account_id).map_err(|e| e.to_string())? {
+                println!("{}", ticket);
+            }
+        },
+        ("balance", Some(account_id)) => {
+            let balance = db.get_account_balance(account_id).map_err(|e| e.to_string())?;
+            println!("{}", json!({ "accountId": account_id, "balance": balance.to_string() }));
+        },
+        ("owner", None) => {
+            let owner_id = db.get_owner_id().map_err(|e| e.to_string())?;
+            println!("{}", json!({ "ownerId": owner_id }));
+        },
+        ("treasury", None) => {
+            let treasury = db.get_treasury().map_err(|e| e.to_string())?;
+            println!("{}", json!({ "treasury": treasury.to_string() }));
+        },
+        ("jackpots", None) => {
+            for jackpot in db.get_jackpots().map_err(|e| e.to_string())? {
+                println!("{}", jackpot);
+            }
+        },
+        ("payouts", None) => {
+            for (jackpot_id, paid_amount) in db.get_jackpot_payouts().map_err(|e| e.to_string())? {
+                println!("{}", json!({ "jackpotId": jackpot_id, "paidAmount": paid_amount.to_string() }));
+            }
+        },
+        ("winners", jackpot_id) => {
+            let jackpot_id = match jackpot_id {
+                None => None,
+                Some(id) => Some(id.parse::<u32>().map_err(|_| format!("Invalid jackpot id {}.", id))?),
+            };
+            for winner in db.get_winners(jackpot_id).map_err(|e| e.to_string())? {
+                println!("{}", winner);
+            }
+        },
+        ("draws", Some(jackpot_id)) => {
+            let jackpot_id = jackpot_id.parse::<u32>().map_err(|_| format!("Invalid jackpot id {}.", jackpot_id))?;
+            for result in db.get_drawed_results(jackpot_id).map_err(|e| e.to_string())? {
+                println!("{}", result);
+            }
+        },
+        _ => return Err(String::from(USAGE)),
+    }
+
+    Ok(())
+}
+
+/// Index every event line of the reader, returns the number of indexed events.
+fn ingest<R: BufRead>(db: &mut Database, reader: R) -> Result<usize, String> {
+    let mut count = 0;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        let events = parse_line(&line).map_err(|e| format!("Line {}: {}", i + 1, e))?;
+        db.apply_events(&events).map_err(|e| format!("Line {}: {}", i + 1, e))?;
+        count += events.len();
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::*;
+
+    const ONE_NEAR: u128 = 1_000_000_000_000_000_000_000_000;
+
+    fn event_line(event: &str, data: serde_json::Value) -> String {
+        versioned_event_line("2.0.0", event, data)
+    }
+
+    fn versioned_event_line(version: &str, event: &str, data: serde_json::Value) -> String {
+        format!("EVENT_JSON:{}", json!({ "standard": "lottery", "version": version, "event": event, "data": [data] }))
+    }
+
+    fn receipt_line(receipt_id: &str, logs: &[String]) -> String {
+        json!({ "receipt_id": receipt_id, "logs": logs }).to_string()
+    }
+
+    fn sample_logs() -> String {
+        [
+            event_line("jackpot_created", json!({ "jackpot_id": 1, "ticket_price": ONE_NEAR.to_string(), "initialized_amount": (10 * ONE_NEAR).to_string(), "claim_duration": "100" })),
+            receipt_line("receipt-1", &[event_line("deposit", json!({ "account_id": "bob_near", "amount": (5 * ONE_NEAR).to_string() }))]),
+            event_line("ticket_bought", json!({ "jackpot_id": 1, "ticket_id": 1, "account_id": "bob_near", "picked_numbers": [1, 2, 3, 4, 5, 6], "amount": ONE_NEAR.to_string(), "fee": "0" })),
+            event_line("ticket_bought", json!({ "jackpot_id": 1, "ticket_id": 2, "account_id": "bob_near", "picked_numbers": [1, 3, 4, 5, 6, 7], "amount": ONE_NEAR.to_string(), "fee": "0" })),
+            json!(event_line("draw_result", json!({ "jackpot_id": 1, "drawed_numbers": [1, 2, 3, 4, 5, 6], "created_time": "11", "game_type": "Standard" }))).to_string(),
+            json!({ "logs": [
+                "Some plain log",
+                event_line("win_tickets_found", json!({ "jackpot_id": 1, "ticket_ids": [1] })),
+                event_line("jackpot_settled", json!({ "jackpot_id": 1, "no_of_win_tickets": 1, "prize_amount": (12 * ONE_NEAR).to_string(), "end_time": "11" })),
+            ] }).to_string(),
+            String::new(),
+            event_line("winner_paid", json!({ "jackpot_id": 1, "ticket_id": 1, "account_id": "bob_near", "amount": (12 * ONE_NEAR).to_string() })),
+            receipt_line("receipt-2", &[event_line("withdraw", json!({ "account_id": "bob_near", "amount": (15 * ONE_NEAR).to_string() }))]),
+        ].join("\n")
+    }
+
+    #[test]
+    fn parse_event_lines() {
+        let events = parse_line(sample_logs().lines().nth(1).unwrap()).unwrap();
+        let deposit = LotteryEvent::Deposit(Deposit { account_id: String::from("bob_near"), amount: 5 * ONE_NEAR });
+        assert_eq!(vec![KeyedEvent { receipt_id: Some(String::from("receipt-1")), log_index: 0, data_index: 0, event: deposit }], events);
+
+        assert!(parse_line("").unwrap().is_empty());
+        assert!(parse_line(&json!({ "standard": "nep171", "version": "1.0.0", "event": "nft_mint", "data": [] }).to_string()).unwrap().is_empty());
+        assert!(parse_line("EVENT_JSON:{").is_err());
+        assert!(parse_line(&versioned_event_line("3.0.0", "deposit", json!({ "account_id": "bob_near", "amount": "1" }))).is_err());
+    }
+
+    #[test]
+    fn parse_version_1_events() {
+        let logs = [
+            versioned_event_line("1.0.0", "jackpot_created", json!({ "jackpot_id": 1, "ticket_price": ONE_NEAR.to_string(), "initialized_amount": "0", "claim_duration": "100" })),
+            receipt_line("receipt-1", &[versioned_event_line("1.0.0", "deposit", json!({ "account_id": "bob_near", "amount": ONE_NEAR.to_string() }))]),
+            versioned_event_line("1.0.0", "ticket_bought", json!({ "jackpot_id": 1, "ticket_id": 1, "account_id": "bob_near", "picked_numbers": [1, 2, 3, 4, 5, 6], "amount": ONE_NEAR.to_string() })),
+            versioned_event_line("1.0.0", "draw_result", json!({ "jackpot_id": 1, "drawed_numbers": [1, 2, 3, 4, 5, 6], "created_time": "11", "force_win": true })),
+            versioned_event_line("1.0.0", "jackpot_settled", json!({ "jackpot_id": 1, "win_ticket_ids": [1], "prize_amount": ONE_NEAR.to_string(), "end_time": "11" })),
+        ].join("\n");
+
+        let mut db = Database::open_in_memory().unwrap();
+        assert_eq!(5, ingest(&mut db, logs.as_bytes()).unwrap());
+
+        assert_eq!(0, db.get_treasury().unwrap());
+        assert_eq!(json!("GuaranteedWinner"), db.get_drawed_results(1).unwrap()[0]["gameType"]);
+        assert_eq!(json!(ONE_NEAR.to_string()), db.get_winners(Some(1)).unwrap()[0]["prizeAmount"]);
+
+        // The fields of version 1 are not read from the later versions
+        assert!(parse_line(&event_line("draw_result", json!({ "jackpot_id": 1, "drawed_numbers": [1, 2, 3, 4, 5, 6], "created_time": "11", "force_win": true }))).is_err());
+    }
+
+    #[test]
+    fn rebuild_state() {
+        let mut db = Database::open_in_memory().unwrap();
+        let count = ingest(&mut db, sample_logs().as_bytes()).unwrap();
+        assert_eq!(9, count);
+
+        // 5 deposited - 2 tickets + 12 prize - 15 withdrawn
+        assert_eq!(0, db.get_account_balance("bob_near").unwrap());
+
+        let tickets = db.get_account_tickets("bob_near").unwrap();
+        assert_eq!(2, tickets.len());
+        assert_eq!(json!([1, 2, 3, 4, 5, 6]), tickets[0]["pickedNumbers"]);
+        assert_eq!(json!((12 * ONE_NEAR).to_string()), tickets[0]["paidAmount"]);
+
+        assert_eq!(vec![(1, 12 * ONE_NEAR)], db.get_jackpot_payouts().unwrap());
+        assert_eq!(1, db.get_winners(Some(1)).unwrap().len());
+        assert!(db.get_winners(Some(2)).unwrap().is_empty());
+
+        let jackpots = db.get_jackpots().unwrap();
+        assert_eq!(json!("0"), jackpots[0]["lockedAmount"]);
+    }
+
+    #[test]
+    fn replay_keyed_events() {
+        let mut db = Database::open_in_memory().unwrap();
+        ingest(&mut db, sample_logs().as_bytes()).unwrap();
+
+        // The deposit and the withdrawal are skipped by their receipt, the rest by their content
+        assert_eq!(9, ingest(&mut db, sample_logs().as_bytes()).unwrap());
+
+        assert_eq!(0, db.get_account_balance("bob_near").unwrap());
+        assert_eq!(2, db.get_account_tickets("bob_near").unwrap().len());
+        assert_eq!(vec![(1, 12 * ONE_NEAR)], db.get_jackpot_payouts().unwrap());
+        assert_eq!(json!("0"), db.get_jackpots().unwrap()[0]["lockedAmount"]);
+    }
+
+    #[test]
+    fn reject_unkeyed_and_underflowing_events() {
+        let mut db = Database::open_in_memory().unwrap();
+        let deposit = event_line("deposit", json!({ "account_id": "bob_near", "amount": ONE_NEAR.to_string() }));
+        assert!(ingest(&mut db, deposit.as_bytes()).unwrap_err().contains("receipt_id"));
+        assert_eq!(0, db.get_account_balance("bob_near").unwrap());
+
+        // A withdrawal over the indexed balance means some deposits were not read
+        let withdraw = event_line("withdraw", json!({ "account_id": "bob_near", "amount": ONE_NEAR.to_string() }));
+        assert!(ingest(&mut db, receipt_line("receipt-1", &[withdraw]).as_bytes()).unwrap_err().contains("below zero"));
+    }
+
+    #[test]
+    fn refund_tickets() {
+        let logs = [
+            event_line("jackpot_created", json!({ "jackpot_id": 1, "ticket_price": ONE_NEAR.to_string(), "initialized_amount": ONE_NEAR.to_string(), "claim_duration": "100" })),
+            receipt_line("receipt-1", &[event_line("deposit", json!({ "account_id": "bob_near", "amount": (2 * ONE_NEAR).to_string() }))]),
+            event_line("ticket_bought", json!({ "jackpot_id": 1, "ticket_id": 1, "account_id": "bob_near", "picked_numbers": [1, 2, 3, 4, 5, 6], "amount": ONE_NEAR.to_string(), "fee": "0" })),
+            event_line("ticket_bought", json!({ "jackpot_id": 1, "ticket_id": 2, "account_id": "bob_near", "picked_numbers": [1, 3, 4, 5, 6, 7], "amount": ONE_NEAR.to_string(), "fee": "0" })),
+            event_line("wind_down_started", json!({ "refund_jackpot_id": 1, "started_time": "11" })),
+            receipt_line("receipt-2", &[
+                event_line("tickets_refunded", json!({ "jackpot_id": 1, "ticket_ids": [1, 2], "amount": ONE_NEAR.to_string(), "fee": "0" })),
+                event_line("jackpot_remainder_released", json!({ "jackpot_id": 1, "amount": ONE_NEAR.to_string() })),
+            ]),
+        ].join("\n");
+
+        let mut db = Database::open_in_memory().unwrap();
+        assert_eq!(7, ingest(&mut db, logs.as_bytes()).unwrap());
+
+        assert_eq!(2 * ONE_NEAR, db.get_account_balance("bob_near").unwrap());
+        assert_eq!(ONE_NEAR, db.get_treasury().unwrap());
+        let jackpots = db.get_jackpots().unwrap();
+        assert_eq!(json!("0"), jackpots[0]["lockedAmount"]);
+        assert_eq!(json!(11), jackpots[0]["endTime"]);
+    }
+
+    #[test]
+    fn collect_fees() {
+        let fee = ONE_NEAR / 20;
+        let logs = [
+            event_line("jackpot_created", json!({ "jackpot_id": 1, "ticket_price": ONE_NEAR.to_string(), "initialized_amount": "0", "claim_duration": "100", "fee_bps": 500 })),
+            receipt_line("receipt-1", &[event_line("deposit", json!({ "account_id": "bob_near", "amount": ONE_NEAR.to_string() }))]),
+            event_line("ticket_bought", json!({ "jackpot_id": 1, "ticket_id": 1, "account_id": "bob_near", "picked_numbers": [1, 2, 3, 4, 5, 6], "amount": ONE_NEAR.to_string(), "fee": fee.to_string() })),
+            receipt_line("receipt-2", &[event_line("treasury_withdrawn", json!({ "account_id": "alice_near", "amount": (fee / 2).to_string() }))]),
+        ].join("\n");
+
+        let mut db = Database::open_in_memory().unwrap();
+        assert_eq!(4, ingest(&mut db, logs.as_bytes()).unwrap());
+
+        assert_eq!(fee / 2, db.get_treasury().unwrap());
+        assert_eq!(json!((ONE_NEAR - fee).to_string()), db.get_jackpots().unwrap()[0]["lockedAmount"]);
+    }
+}
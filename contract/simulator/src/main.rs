@@ -0,0 +1,254 @@
+/*
+ * Monte Carlo simulator of the lottery economics.
+ *
+ * It plays rounds with the rules of the lottery core: players buy tickets, a result is drawn
+ * and the pot is paid out to the prize tiers, what is left rolls over to the next round.
+ *
+ * Usage:
+ *   lottery-simulator [OPTIONS]
+ *
+ *   --rounds N                 Number of rounds (1000)
+ *   --players N                Players per round (100)
+ *   --tickets-per-player N     Tickets bought by each player (1)
+ *   --ticket-price AMOUNT      Ticket price in yoctoNEAR (1 NEAR)
+ *   --quick-pick-ratio F       Share of random tickets, the others pick birthday numbers (0.5)
+ *   --tier MATCHES:BPS         Prize tier paying a share of the pot in basis points
+ *   --tier MATCHES:=AMOUNT     Prize tier paying a fixed amount to each winner
+ *   --house-fee-bps N          Fee kept from the sales in basis points (0)
+ *   --initial-pot AMOUNT       Pot of the first round (0)
+ *   --seed N                   Seed of the players and drawings (1)
+ *   --format csv|json          Output format (csv)
+ *
+ * Without any --tier the whole pot goes to the 6 matches winners, like the contract. The CSV
+ * has one line per round and the summary is printed to stderr, the JSON holds both.
+ *
+ */
+use std::process;
+
+use serde_json::{json, Value};
+
+use crate::simulation::*;
+
+mod simulation;
+
+#[derive(Debug, PartialEq)]
+enum Format {
+    Csv,
+    Json,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let (config, format) = parse_args(args)?;
+
+    match format {
+        Format::Csv => {
+            println!("{}", csv_header(&config));
+            let summary = simulate(&config, |round| println!("{}", csv_row(round)))?;
+            eprintln!("{}", summary_to_json(&summary));
+        },
+        Format::Json => {
+            let mut rounds = Vec::new();
+            let summary = simulate(&config, |round| rounds.push(round_to_json(round)))?;
+            println!("{}", json!({ "rounds": rounds, "summary": summary_to_json(&summary) }));
+        },
+    }
+
+    Ok(())
+}
+
+fn parse_args(args: &[String]) -> Result<(Config, Format), String> {
+    let mut config = Config::default();
+    let mut tiers = Vec::new();
+    let mut format = Format::Csv;
+
+    let mut args = args.iter();
+    while let Some(name) = args.next() {
+        let value = args.next().ok_or_else(|| format!("Missing value of {}.", name))?;
+        match name.as_str() {
+            "--rounds" => config.rounds = parse_value(name, value)?,
+            "--players" => config.players = parse_value(name, value)?,
+            "--tickets-per-player" => config.tickets_per_player = parse_value(name, value)?,
+            "--ticket-price" => config.ticket_price = parse_value(name, value)?,
+            "--quick-pick-ratio" => config.quick_pick_ratio = parse_value(name, value)?,
+            "--tier" => tiers.push(parse_tier(value)?),
+            "--house-fee-bps" => config.house_fee_bps = parse_value(name, value)?,
+            "--initial-pot" => config.initial_pot = parse_value(name, value)?,
+            "--seed" => config.seed = parse_value(name, value)?,
+            "--format" => format = match value.as_str() {
+                "csv" => Format::Csv,
+                "json" => Format::Json,
+                _ => return Err(format!("Unknown format {}.", value)),
+            },
+            _ => return Err(format!("Unknown option {}.", name)),
+        }
+    }
+
+    if !tiers.is_empty() {
+        config.tiers = tiers;
+    }
+    config.validate()?;
+
+    Ok((config, format))
+}
+
+fn parse_value<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid value {} of {}.", value, name))
+}
+
+fn parse_tier(value: &str) -> Result<Tier, String> {
+    let invalid = || format!("Invalid tier {}, expected MATCHES:BPS or MATCHES:=AMOUNT.", value);
+    let mut parts = value.splitn(2, ':');
+    let matches = parts.next().and_then(|m| m.parse().ok()).ok_or_else(invalid)?;
+    let prize = parts.next().ok_or_else(invalid)?;
+
+    let prize = match prize.strip_prefix('=') {
+        Some(amount) => Prize::Fixed(amount.parse().map_err(|_| invalid())?),
+        None => Prize::Share(prize.parse().map_err(|_| invalid())?),
+    };
+
+    Ok(Tier { matches, prize })
+}
+
+fn csv_header(config: &Config) -> String {
+    let mut columns = vec![String::from("round"), String::from("tickets"), String::from("sales"), String::from("house_fee"), String::from("pot"), String::from("drawed_numbers")];
+    for tier in &config.tiers {
+        columns.push(format!("winners_{}", tier.matches));
+        columns.push(format!("payout_{}", tier.matches));
+    }
+    for column in &["payout", "rolled_over", "shortfall", "solvent"] {
+        columns.push(column.to_string());
+    }
+    columns.join(",")
+}
+
+fn csv_row(round: &Round) -> String {
+    let drawed_numbers: Vec<String> = round.drawed_numbers.iter().map(|n| n.to_string()).collect();
+    let mut columns = vec![
+        round.round.to_string(),
+        round.no_of_tickets.to_string(),
+        round.sales.to_string(),
+        round.house_fee.to_string(),
+        round.pot.to_string(),
+        drawed_numbers.join(" "),
+    ];
+    for tier in &round.tiers {
+        columns.push(tier.winners.to_string());
+        columns.push(tier.payout.to_string());
+    }
+    columns.push(round.payout.to_string());
+    columns.push(round.rolled_over.to_string());
+    columns.push(round.shortfall.to_string());
+    columns.push(round.is_solvent().to_string());
+    columns.join(",")
+}
+
+// Amounts are yoctoNEAR, they are written as strings like in the contract views
+fn tiers_to_json(tiers: &[TierResult]) -> Value {
+    tiers.iter()
+        .map(|tier| json!({ "matches": tier.matches, "winners": tier.winners, "payout": tier.payout.to_string() }))
+        .collect()
+}
+
+fn round_to_json(round: &Round) -> Value {
+    json!({
+        "round": round.round,
+        "noOfTickets": round.no_of_tickets,
+        "sales": round.sales.to_string(),
+        "houseFee": round.house_fee.to_string(),
+        "pot": round.pot.to_string(),
+        "drawedNumbers": round.drawed_numbers,
+        "tiers": tiers_to_json(&round.tiers),
+        "payout": round.payout.to_string(),
+        "rolledOver": round.rolled_over.to_string(),
+        "shortfall": round.shortfall.to_string(),
+        "solvent": round.is_solvent(),
+    })
+}
+
+fn summary_to_json(summary: &Summary) -> Value {
+    json!({
+        "rounds": summary.rounds,
+        "noOfTickets": summary.no_of_tickets,
+        "sales": summary.sales.to_string(),
+        "houseFee": summary.house_fee.to_string(),
+        "payout": summary.payout.to_string(),
+        "tiers": tiers_to_json(&summary.tiers),
+        "expectedValue": summary.expected_value(),
+        "houseEdge": summary.house_edge(),
+        "maxPot": summary.max_pot.to_string(),
+        "finalPot": summary.final_pot.to_string(),
+        "insolventRounds": summary.insolvent_rounds,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_options() {
+        let (config, format) = parse_args(&args("--rounds 10 --tier 6:8000 --tier 3:=5 --format json")).unwrap();
+        assert_eq!(10, config.rounds);
+        assert_eq!(vec![Tier { matches: 6, prize: Prize::Share(8000) }, Tier { matches: 3, prize: Prize::Fixed(5) }], config.tiers);
+        assert_eq!(Format::Json, format);
+
+        assert!(parse_args(&args("--tier 6:8000 --tier 5:3000")).is_err());
+        assert!(parse_args(&args("--quick-pick-ratio 2")).is_err());
+        assert!(parse_args(&args("--rounds")).is_err());
+    }
+
+    #[test]
+    fn money_is_conserved() {
+        let config = Config {
+            rounds: 200,
+            players: 50,
+            ticket_price: 100,
+            tiers: vec![
+                Tier { matches: 6, prize: Prize::Share(7000) },
+                Tier { matches: 3, prize: Prize::Fixed(20) },
+                Tier { matches: 2, prize: Prize::Share(1000) },
+            ],
+            house_fee_bps: 500,
+            initial_pot: 1000,
+            ..Config::default()
+        };
+
+        let mut rounds = Vec::new();
+        let summary = simulate(&config, |round| rounds.push(round.clone())).unwrap();
+        assert_eq!(200, rounds.len());
+        assert_eq!(200 * 50, summary.no_of_tickets);
+        assert_eq!(summary.sales * 500 / 10_000, summary.house_fee);
+
+        // Everything sold is either kept as fee, paid out or still in the pot
+        assert_eq!(config.initial_pot + summary.sales, summary.house_fee + summary.payout + summary.final_pot);
+        assert!(summary.tiers[2].winners > 0);
+
+        // The same seed plays the same rounds
+        let mut replayed = Vec::new();
+        simulate(&config, |round| replayed.push(round.clone())).unwrap();
+        assert_eq!(rounds, replayed);
+    }
+
+    #[test]
+    fn csv_output() {
+        let config = Config { rounds: 1, players: 3, ..Config::default() };
+        let header = csv_header(&config);
+        assert_eq!("round,tickets,sales,house_fee,pot,drawed_numbers,winners_6,payout_6,payout,rolled_over,shortfall,solvent", header);
+
+        let mut rows = Vec::new();
+        simulate(&config, |round| rows.push(csv_row(round))).unwrap();
+        assert_eq!(header.split(',').count(), rows[0].split(',').count());
+    }
+}
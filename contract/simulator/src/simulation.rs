@@ -0,0 +1,328 @@
+use lottery_core::*;
+
+// Manual pickers favour birthdays, so their numbers are drawn from 1 to 31
+const MAX_MANUAL_NUMBER: u64 = 31;
+// Seeds are kept in the range of block timestamps, like the contract
+const BASE_SEED: u64 = 1_640_995_200_000_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Prize {
+    /// Basis points of the pot, split between the winners of the tier.
+    Share(u32),
+    /// Fixed amount paid to each winner of the tier.
+    Fixed(u128),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tier {
+    pub matches: u32,
+    pub prize: Prize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub rounds: u64,
+    pub players: u64,
+    pub tickets_per_player: u64,
+    pub ticket_price: u128,
+    pub quick_pick_ratio: f64,
+    pub tiers: Vec<Tier>,
+    pub house_fee_bps: u32,
+    pub initial_pot: u128,
+    pub seed: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            rounds: 1_000,
+            players: 100,
+            tickets_per_player: 1,
+            ticket_price: 1_000_000_000_000_000_000_000_000,
+            quick_pick_ratio: 0.5,
+            // The contract pays the whole pot to the 6 matches winners
+            tiers: vec![Tier { matches: 6, prize: Prize::Share(10_000) }],
+            house_fee_bps: 0,
+            initial_pot: 0,
+            seed: 1,
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.quick_pick_ratio) {
+            return Err(String::from("The quick pick ratio must be between 0 and 1."));
+        }
+
+        if self.house_fee_bps as u128 > BASIS_POINTS {
+            return Err(String::from("The house fee cannot be over 10000 basis points."));
+        }
+
+        let mut total_share: u128 = 0;
+        for (i, tier) in self.tiers.iter().enumerate() {
+            if tier.matches < 1 || tier.matches > 6 {
+                return Err(format!("Invalid tier of {} matches.", tier.matches));
+            }
+
+            if self.tiers[..i].iter().any(|t| t.matches == tier.matches) {
+                return Err(format!("The tier of {} matches is duplicated.", tier.matches));
+            }
+
+            if let Prize::Share(share_bps) = tier.prize {
+                total_share += share_bps as u128;
+            }
+        }
+
+        if total_share > BASIS_POINTS {
+            return Err(String::from("The tier shares cannot be over 10000 basis points."));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TierResult {
+    pub matches: u32,
+    pub winners: u64,
+    pub payout: u128,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Round {
+    pub round: u64,
+    pub no_of_tickets: u64,
+    pub sales: u128,
+    pub house_fee: u128,
+    /// Pot before paying the winners, the rolled over amount included.
+    pub pot: u128,
+    pub drawed_numbers: [u8; 6],
+    pub tiers: Vec<TierResult>,
+    pub payout: u128,
+    pub rolled_over: u128,
+    /// Fixed prizes which the pot could not cover.
+    pub shortfall: u128,
+}
+
+impl Round {
+    pub fn is_solvent(&self) -> bool {
+        self.shortfall == 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub rounds: u64,
+    pub no_of_tickets: u64,
+    pub sales: u128,
+    pub house_fee: u128,
+    pub payout: u128,
+    pub tiers: Vec<TierResult>,
+    pub max_pot: u128,
+    pub final_pot: u128,
+    pub insolvent_rounds: u64,
+}
+
+impl Summary {
+    /// Average payout of a ticket.
+    pub fn expected_value(&self) -> f64 {
+        if self.no_of_tickets == 0 {
+            return 0.0;
+        }
+        self.payout as f64 / self.no_of_tickets as f64
+    }
+
+    /// Share of the sales which is not paid back to the players.
+    pub fn house_edge(&self) -> f64 {
+        if self.sales == 0 {
+            return 0.0;
+        }
+        1.0 - self.payout as f64 / self.sales as f64
+    }
+}
+
+// SplitMix64, good enough to generate players and seeds without extra dependencies
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_below(&mut self, max: u64) -> u64 {
+        self.next_u64() % max
+    }
+
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn pick_numbers(&mut self, max: u64) -> [u8; 6] {
+        let mut numbers = [0u8; 6];
+        let mut i = 0;
+        while i < 6 {
+            let number = self.next_below(max) as u8 + 1;
+            if !numbers[..i].contains(&number) {
+                numbers[i] = number;
+                i += 1;
+            }
+        }
+        numbers
+    }
+}
+
+struct SimulatedRuntime {
+    time: u64,
+    seed: u64,
+}
+
+impl Clock for SimulatedRuntime {
+    fn now(&self) -> u64 {
+        self.time
+    }
+}
+
+impl Randomness for SimulatedRuntime {
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+pub struct Simulator {
+    config: Config,
+    rng: Rng,
+    pot: u128,
+    round: u64,
+}
+
+impl Simulator {
+    pub fn new(config: Config) -> Result<Self, String> {
+        config.validate()?;
+
+        Ok(Self {
+            rng: Rng::new(config.seed),
+            pot: config.initial_pot,
+            round: 0,
+            config,
+        })
+    }
+
+    /// Sell the tickets of a round, draw it and pay the winners.
+    pub fn next_round(&mut self) -> Round {
+        self.round += 1;
+
+        let no_of_tickets = self.config.players * self.config.tickets_per_player;
+        let tickets: Vec<[u8; 6]> = (0..no_of_tickets).map(|_| self.pick_ticket()).collect();
+
+        let sales = self.config.ticket_price * no_of_tickets as u128;
+        let house_fee = house_fee(sales, self.config.house_fee_bps);
+        self.pot += sales - house_fee;
+        let pot = self.pot;
+
+        let runtime = SimulatedRuntime {
+            time: BASE_SEED + self.round,
+            seed: BASE_SEED + self.rng.next_below(1_000_000_000_000_000_000),
+        };
+        let result = draw(&runtime, &runtime, &tickets[..], false);
+
+        let mut winners_by_matches = [0u64; 7];
+        for picked_numbers in &tickets {
+            winners_by_matches[count_matches(numbers_to_bitmask(picked_numbers), result.drawed_mask) as usize] += 1;
+        }
+
+        // Fixed prizes come first, the shares are taken from what is left
+        let mut shortfall = 0;
+        let mut tier_results: Vec<TierResult> = self.config.tiers.iter()
+            .map(|tier| TierResult { matches: tier.matches, winners: winners_by_matches[tier.matches as usize], payout: 0 })
+            .collect();
+
+        for (tier, tier_result) in self.config.tiers.iter().zip(tier_results.iter_mut()) {
+            if let Prize::Fixed(amount) = tier.prize {
+                let owed = amount * tier_result.winners as u128;
+                tier_result.payout = std::cmp::min(owed, self.pot);
+                shortfall += owed - tier_result.payout;
+                self.pot -= tier_result.payout;
+            }
+        }
+
+        let shared_pot = self.pot;
+        for (tier, tier_result) in self.config.tiers.iter().zip(tier_results.iter_mut()) {
+            if let Prize::Share(share_bps) = tier.prize {
+                let prize = split_prize(shared_pot * share_bps as u128 / BASIS_POINTS, tier_result.winners);
+                tier_result.payout = prize * tier_result.winners as u128;
+                self.pot -= tier_result.payout;
+            }
+        }
+
+        Round {
+            round: self.round,
+            no_of_tickets,
+            sales,
+            house_fee,
+            pot,
+            drawed_numbers: result.drawed_numbers,
+            payout: tier_results.iter().map(|t| t.payout).sum(),
+            tiers: tier_results,
+            rolled_over: self.pot,
+            shortfall,
+        }
+    }
+
+    fn pick_ticket(&mut self) -> [u8; 6] {
+        let max = if self.rng.next_f64() < self.config.quick_pick_ratio {
+            MAX_DRAWING_NUMBER as u64
+        }
+        else {
+            MAX_MANUAL_NUMBER
+        };
+
+        validate_numbers(&self.rng.pick_numbers(max)).unwrap()
+    }
+}
+
+/// Run every round of the configuration, `on_round` gets each one as it is played.
+pub fn simulate<F: FnMut(&Round)>(config: &Config, mut on_round: F) -> Result<Summary, String> {
+    let mut simulator = Simulator::new(config.clone())?;
+    let mut summary = Summary {
+        rounds: config.rounds,
+        no_of_tickets: 0,
+        sales: 0,
+        house_fee: 0,
+        payout: 0,
+        tiers: config.tiers.iter().map(|tier| TierResult { matches: tier.matches, winners: 0, payout: 0 }).collect(),
+        max_pot: 0,
+        final_pot: config.initial_pot,
+        insolvent_rounds: 0,
+    };
+
+    for _ in 0..config.rounds {
+        let round = simulator.next_round();
+
+        summary.no_of_tickets += round.no_of_tickets;
+        summary.sales += round.sales;
+        summary.house_fee += round.house_fee;
+        summary.payout += round.payout;
+        for (total, tier) in summary.tiers.iter_mut().zip(&round.tiers) {
+            total.winners += tier.winners;
+            total.payout += tier.payout;
+        }
+        summary.max_pot = std::cmp::max(summary.max_pot, round.pot);
+        summary.final_pot = round.rolled_over;
+        if !round.is_solvent() {
+            summary.insolvent_rounds += 1;
+        }
+
+        on_round(&round);
+    }
+
+    Ok(summary)
+}
@@ -6,7 +6,31 @@ pub struct AccountInfo {
     pub balance: Balance,
     // TODO: Should be defined as reference type
     pub ticket_ids: Vector<TicketId>,
-    created_time: Timestamp
+    created_time: Timestamp,
+    // NEP-145 deposit paying for the storage used by the account and its tickets
+    pub storage_deposit: Balance,
+    pub storage_usage: StorageUsage,
+}
+
+// Layout of the accounts stored before the storage deposit was added
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AccountInfoV0 {
+    pub balance: Balance,
+    pub ticket_ids: Vector<TicketId>,
+    pub created_time: Timestamp,
+}
+
+// The contract paid for the storage of these accounts, they start with nothing charged
+impl From<AccountInfoV0> for AccountInfo {
+    fn from(account_info: AccountInfoV0) -> Self {
+        Self {
+            balance: account_info.balance,
+            ticket_ids: account_info.ticket_ids,
+            created_time: account_info.created_time,
+            storage_deposit: 0,
+            storage_usage: 0,
+        }
+    }
 }
 
 impl Serialize for AccountInfo {
@@ -14,10 +38,12 @@ impl Serialize for AccountInfo {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("AccountInfo", 3)?;
+        let mut state = serializer.serialize_struct("AccountInfo", 5)?;
         state.serialize_field("balance", &self.balance.to_string())?;
         state.serialize_field("ticketIds", &self.ticket_ids.to_vec())?;
         state.serialize_field("createdTime", &self.created_time)?;
+        state.serialize_field("storageDeposit", &self.storage_deposit.to_string())?;
+        state.serialize_field("storageUsage", &self.storage_usage)?;
         state.end()
     }
 }
@@ -26,8 +52,32 @@ impl AccountInfo {
     pub fn new(key: AccountId) -> Self {
         Self {
             balance: 0,
-            ticket_ids: Vector::new(format!("ta{}", key).as_bytes()),
+            ticket_ids: Vector::new(StorageKey::AccountTicketIds { account_hash: hash_account_id(&key) }),
             created_time: get_time_now(),
+            storage_deposit: 0,
+            storage_usage: 0,
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_storage_balance(&self) -> StorageBalance {
+        let used_amount = Balance::from(self.storage_usage) * env::storage_byte_cost();
+        StorageBalance {
+            total: self.storage_deposit.into(),
+            available: self.storage_deposit.saturating_sub(used_amount).into(),
+        }
+    }
+
+    /// Charge the storage written since `initial_storage_usage` to the account.
+    pub fn charge_storage(&mut self, initial_storage_usage: StorageUsage) {
+        self.storage_usage += env::storage_usage().saturating_sub(initial_storage_usage);
+
+        let used_amount = Balance::from(self.storage_usage) * env::storage_byte_cost();
+        assert!(self.storage_deposit >= used_amount, "{}", LotteryError::InsufficientStorageBalance);
+    }
+
+    /// Move the ticket ids stored under the ASCII prefix used before `StorageKey`.
+    /// Returns false when they are already there.
+    pub(crate) fn move_to_storage_key(&mut self, account_id: &AccountId) -> bool {
+        move_vector(&mut self.ticket_ids, StorageKey::AccountTicketIds { account_hash: hash_account_id(account_id) })
+    }
+}
@@ -0,0 +1,156 @@
+use crate::*;
+use serde_json::{json, Value};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum AuditAction {
+    JackpotCreated { jackpot_id: JackpotId, initialized_amount: Balance },
+    JackpotDrawn { jackpot_id: JackpotId, game_type: GameType },
+    PauseChanged { jackpot_id: Option<JackpotId>, scope: String, paused: bool },
+    WindDownStarted { refund_jackpot_id: Option<JackpotId> },
+    ConfigScheduled { ticket_price: Balance, claim_duration: Timestamp, game_type: GameType, fee_bps: u32, effective_time: Timestamp },
+    ConfigCancelled { effective_time: Timestamp },
+    RoleGranted { role: Role, account_id: AccountId },
+    RoleRevoked { role: Role, account_id: AccountId },
+    OwnershipProposed { owner_id: AccountId },
+    OwnershipProposalCancelled { owner_id: AccountId },
+    OwnerChanged { old_owner_id: AccountId, new_owner_id: AccountId },
+    MultisigChanged { approvers: Vec<AccountId>, threshold: u32 },
+    ProposalExecuted { proposal_id: ProposalId },
+    UpgradeTimelockChanged { upgrade_timelock: Timestamp },
+    UpgradeStaged { code_hash: CryptoHash },
+    UpgradeCancelled { code_hash: CryptoHash },
+    UpgradeDeployed { code_hash: CryptoHash },
+    TreasuryWithdrawn { receiver_id: AccountId, amount: Balance },
+}
+
+impl AuditAction {
+    pub fn get_name(&self) -> &str {
+        match self {
+            AuditAction::JackpotCreated { .. } => "jackpot_created",
+            AuditAction::JackpotDrawn { .. } => "jackpot_drawn",
+            AuditAction::PauseChanged { .. } => "pause_changed",
+            AuditAction::WindDownStarted { .. } => "wind_down_started",
+            AuditAction::ConfigScheduled { .. } => "config_scheduled",
+            AuditAction::ConfigCancelled { .. } => "config_cancelled",
+            AuditAction::RoleGranted { .. } => "role_granted",
+            AuditAction::RoleRevoked { .. } => "role_revoked",
+            AuditAction::OwnershipProposed { .. } => "ownership_proposed",
+            AuditAction::OwnershipProposalCancelled { .. } => "ownership_proposal_cancelled",
+            AuditAction::OwnerChanged { .. } => "owner_changed",
+            AuditAction::MultisigChanged { .. } => "multisig_changed",
+            AuditAction::ProposalExecuted { .. } => "proposal_executed",
+            AuditAction::UpgradeTimelockChanged { .. } => "upgrade_timelock_changed",
+            AuditAction::UpgradeStaged { .. } => "upgrade_staged",
+            AuditAction::UpgradeCancelled { .. } => "upgrade_cancelled",
+            AuditAction::UpgradeDeployed { .. } => "upgrade_deployed",
+            AuditAction::TreasuryWithdrawn { .. } => "treasury_withdrawn",
+        }
+    }
+
+    // Written like the data of the event logged for the same action
+    pub fn get_params(&self) -> Value {
+        match self {
+            AuditAction::JackpotCreated { jackpot_id, initialized_amount } => json!({
+                "jackpot_id": jackpot_id,
+                "initialized_amount": U128::from(*initialized_amount),
+            }),
+            AuditAction::JackpotDrawn { jackpot_id, game_type } => json!({
+                "jackpot_id": jackpot_id,
+                "game_type": game_type,
+            }),
+            AuditAction::PauseChanged { jackpot_id, scope, paused } => json!({
+                "jackpot_id": jackpot_id,
+                "scope": scope,
+                "paused": paused,
+            }),
+            AuditAction::WindDownStarted { refund_jackpot_id } => json!({
+                "refund_jackpot_id": refund_jackpot_id,
+            }),
+            AuditAction::ConfigScheduled { ticket_price, claim_duration, game_type, fee_bps, effective_time } => json!({
+                "ticket_price": U128::from(*ticket_price),
+                "claim_duration": U64::from(*claim_duration),
+                "game_type": game_type,
+                "fee_bps": fee_bps,
+                "effective_time": U64::from(*effective_time),
+            }),
+            AuditAction::ConfigCancelled { effective_time } => json!({
+                "effective_time": U64::from(*effective_time),
+            }),
+            AuditAction::RoleGranted { role, account_id } | AuditAction::RoleRevoked { role, account_id } => json!({
+                "role": role,
+                "account_id": account_id,
+            }),
+            AuditAction::OwnershipProposed { owner_id } | AuditAction::OwnershipProposalCancelled { owner_id } => json!({
+                "owner_id": owner_id,
+            }),
+            AuditAction::OwnerChanged { old_owner_id, new_owner_id } => json!({
+                "old_owner_id": old_owner_id,
+                "new_owner_id": new_owner_id,
+            }),
+            AuditAction::MultisigChanged { approvers, threshold } => json!({
+                "approvers": approvers,
+                "threshold": threshold,
+            }),
+            AuditAction::ProposalExecuted { proposal_id } => json!({
+                "proposal_id": proposal_id,
+            }),
+            AuditAction::UpgradeTimelockChanged { upgrade_timelock } => json!({
+                "upgrade_timelock": U64::from(*upgrade_timelock),
+            }),
+            AuditAction::UpgradeStaged { code_hash } | AuditAction::UpgradeCancelled { code_hash } | AuditAction::UpgradeDeployed { code_hash } => json!({
+                "code_hash": Base58CryptoHash::from(*code_hash),
+            }),
+            AuditAction::TreasuryWithdrawn { receiver_id, amount } => json!({
+                "receiver_id": receiver_id,
+                "amount": U128::from(*amount),
+            }),
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AuditEntry {
+    // The caller, the last approver for an action run by a proposal
+    pub account_id: AccountId,
+    pub created_time: Timestamp,
+    pub action: AuditAction,
+}
+
+impl Serialize for AuditEntry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("AuditEntry", 4)?;
+        state.serialize_field("accountId", &self.account_id)?;
+        state.serialize_field("createdTime", &self.created_time)?;
+        state.serialize_field("action", self.action.get_name())?;
+        state.serialize_field("params", &self.action.get_params())?;
+        state.end()
+    }
+}
+
+// Every privileged action is appended to the audit log, the entries are never changed or removed
+#[near_bindgen]
+impl Lottery {
+    pub fn get_audit_log_length(&self) -> u64 {
+        self.audit_log.len()
+    }
+
+    /// The entries from the oldest one.
+    pub fn get_audit_log(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<AuditEntry> {
+        get_page_range(self.audit_log.len(), from_index, limit)
+            .map(|i| self.audit_log.get(i).unwrap())
+            .collect()
+    }
+}
+
+impl Lottery {
+    pub(crate) fn record_audit(&mut self, action: AuditAction) {
+        self.audit_log.push(&AuditEntry {
+            account_id: env::predecessor_account_id(),
+            created_time: get_time_now(),
+            action,
+        });
+    }
+}
@@ -0,0 +1,133 @@
+use crate::*;
+
+// 1 day in nanoseconds
+pub const MIN_CONFIG_DELAY: Timestamp = 24 * 60 * 60 * 1_000_000_000;
+// 7 days in nanoseconds, winners need time to notice they won
+pub const MIN_CLAIM_DURATION: Timestamp = 7 * 24 * 60 * 60 * 1_000_000_000;
+
+// Parameters of the next jackpots, each jackpot keeps those it was created with
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct JackpotConfig {
+    pub ticket_price: Balance,
+    // How long winners have to claim their prize after the jackpot closes
+    pub claim_duration: Timestamp,
+    pub game_type: GameType,
+    // Share of each ticket price paid into the treasury, in basis points
+    pub fee_bps: u32,
+}
+
+impl Default for JackpotConfig {
+    fn default() -> Self {
+        Self {
+            ticket_price: ONE_NEAR,
+            claim_duration: DEFAULT_CLAIM_DURATION,
+            game_type: GameType::Standard,
+            fee_bps: 0,
+        }
+    }
+}
+
+impl Serialize for JackpotConfig {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("JackpotConfig", 4)?;
+        state.serialize_field("ticketPrice", &self.ticket_price.to_string())?;
+        state.serialize_field("claimDuration", &self.claim_duration)?;
+        state.serialize_field("gameType", &self.game_type)?;
+        state.serialize_field("feeBps", &self.fee_bps)?;
+        state.end()
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct QueuedConfig {
+    pub config: JackpotConfig,
+    pub effective_time: Timestamp,
+}
+
+impl Serialize for QueuedConfig {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("QueuedConfig", 2)?;
+        state.serialize_field("config", &self.config)?;
+        state.serialize_field("effectiveTime", &self.effective_time)?;
+        state.end()
+    }
+}
+
+// A change of the parameters is announced at least `MIN_CONFIG_DELAY` ahead, and only applies to
+// the jackpots created from its effective time.
+#[near_bindgen]
+impl Lottery {
+    /// The parameters a jackpot created now would get.
+    pub fn get_config(&self) -> JackpotConfig {
+        match &self.queued_config {
+            Some(queued_config) if queued_config.effective_time <= get_time_now() => queued_config.config.clone(),
+            _ => self.config.clone(),
+        }
+    }
+
+    pub fn get_queued_config(&self) -> Option<&QueuedConfig> {
+        self.queued_config.as_ref()
+    }
+
+    /// Queue new parameters, they replace any queued change which is not in force yet.
+    pub fn schedule_config(&mut self, ticket_price: U128, claim_duration: U64, game_type: GameType, fee_bps: u32, effective_time: U64) {
+        // Check account right (The caller must be an operator)
+        self.assert_role(Role::Operator);
+        self.assert_no_multisig();
+
+        self.internal_schedule_config(ticket_price.into(), claim_duration.into(), game_type, fee_bps, effective_time.into());
+    }
+
+    pub fn cancel_queued_config(&mut self) {
+        // Check account right (The caller must be an operator)
+        self.assert_role(Role::Operator);
+        self.apply_queued_config();
+
+        let queued_config = self.queued_config.take().unwrap_or_else(|| panic!("{}", LotteryError::NoQueuedConfig));
+        log_config_cancelled(&queued_config);
+        self.record_audit(AuditAction::ConfigCancelled { effective_time: queued_config.effective_time });
+    }
+}
+
+impl Lottery {
+    pub(crate) fn internal_schedule_config(&mut self, ticket_price: Balance, claim_duration: Timestamp, game_type: GameType, fee_bps: u32, effective_time: Timestamp) {
+        self.apply_queued_config();
+
+        assert!(effective_time >= get_time_now() + MIN_CONFIG_DELAY, "{}", LotteryError::ConfigTooEarly);
+        assert!(fee_bps <= MAX_FEE_BPS, "{}", LotteryError::InvalidFee);
+        assert!(ticket_price > 0, "{}", LotteryError::InvalidTicketPrice);
+        assert!(claim_duration >= MIN_CLAIM_DURATION, "{}", LotteryError::ClaimDurationTooShort);
+
+        let queued_config = QueuedConfig {
+            config: JackpotConfig {
+                ticket_price,
+                claim_duration,
+                game_type,
+                fee_bps,
+            },
+            effective_time,
+        };
+        log_config_scheduled(&queued_config);
+        self.record_audit(AuditAction::ConfigScheduled {
+            ticket_price: queued_config.config.ticket_price,
+            claim_duration: queued_config.config.claim_duration,
+            game_type: queued_config.config.game_type,
+            fee_bps: queued_config.config.fee_bps,
+            effective_time: queued_config.effective_time,
+        });
+        self.queued_config = Some(queued_config);
+    }
+
+    // Move the queued change in force into the current parameters
+    pub(crate) fn apply_queued_config(&mut self) {
+        if matches!(&self.queued_config, Some(queued_config) if queued_config.effective_time <= get_time_now()) {
+            self.config = self.queued_config.take().unwrap().config;
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::*;
+use serde_json::{json, Value};
+
+// NEP-297 event log, see https://nomicon.io/Standards/EventsFormat
+const EVENT_STANDARD: &str = "lottery";
+// The major version changes with the data of an event. Version 2 added the ticket fee and the game
+// type of the drawings, and logs the winners per settle batch.
+const EVENT_VERSION: &str = "2.0.0";
+
+fn log_event(event: &str, data: Value) {
+    let event_log = json!({
+        "standard": EVENT_STANDARD,
+        "version": EVENT_VERSION,
+        "event": event,
+        "data": [data],
+    });
+
+    env::log(format!("EVENT_JSON:{}", event_log).as_bytes());
+}
+
+pub(crate) fn log_deposit(account_id: &AccountId, amount: Balance) {
+    log_event("deposit", json!({
+        "account_id": account_id,
+        "amount": U128::from(amount),
+    }));
+}
+
+pub(crate) fn log_withdraw(account_id: &AccountId, amount: Balance) {
+    log_event("withdraw", json!({
+        "account_id": account_id,
+        "amount": U128::from(amount),
+    }));
+}
+
+pub(crate) fn log_jackpot_created(jackpot: &Jackpot) {
+    log_event("jackpot_created", json!({
+        "jackpot_id": jackpot.id,
+        "ticket_price": U128::from(jackpot.ticket_price),
+        "initialized_amount": U128::from(jackpot.locked_amount),
+        "claim_duration": U64::from(jackpot.claim_duration),
+        "game_type": jackpot.game_type,
+        "fee_bps": jackpot.fee_bps,
+    }));
+}
+
+pub(crate) fn log_ticket_bought(ticket: &Ticket, price: Balance, fee: Balance) {
+    log_event("ticket_bought", json!({
+        "jackpot_id": ticket.jackpot_id,
+        "ticket_id": ticket.id,
+        "account_id": ticket.account_id,
+        "picked_numbers": ticket.picked_numbers,
+        "amount": U128::from(price),
+        "fee": U128::from(fee),
+    }));
+}
+
+pub(crate) fn log_draw_result(jackpot_id: JackpotId, result: &DrawingResult, game_type: GameType) {
+    log_event("draw_result", json!({
+        "jackpot_id": jackpot_id,
+        "drawed_numbers": result.drawed_numbers,
+        "created_time": U64::from(result.created_time),
+        "seed": U64::from(result.seed),
+        "no_of_tickets": result.no_of_tickets,
+        "lucky_ticket_index": result.lucky_ticket_index,
+        "game_type": game_type,
+    }));
+}
+
+pub(crate) fn log_win_tickets_found(jackpot_id: JackpotId, ticket_ids: &[TicketId]) {
+    log_event("win_tickets_found", json!({
+        "jackpot_id": jackpot_id,
+        "ticket_ids": ticket_ids,
+    }));
+}
+
+// The winners are logged by each settle batch, only their number is logged here
+pub(crate) fn log_jackpot_settled(jackpot: &Jackpot) {
+    log_event("jackpot_settled", json!({
+        "jackpot_id": jackpot.id,
+        "no_of_win_tickets": jackpot.win_ticket_ids.len(),
+        "prize_amount": U128::from(jackpot.prize_amount),
+        "end_time": jackpot.end_time.map(U64::from),
+    }));
+}
+
+pub(crate) fn log_winner_paid(ticket: &Ticket, amount: Balance) {
+    log_event("winner_paid", json!({
+        "jackpot_id": ticket.jackpot_id,
+        "ticket_id": ticket.id,
+        "account_id": ticket.account_id,
+        "amount": U128::from(amount),
+    }));
+}
+
+pub(crate) fn log_prize_rolled_over(from_jackpot_id: JackpotId, to_jackpot_id: JackpotId, amount: Balance) {
+    log_event("prize_rolled_over", json!({
+        "from_jackpot_id": from_jackpot_id,
+        "to_jackpot_id": to_jackpot_id,
+        "amount": U128::from(amount),
+    }));
+}
+
+pub(crate) fn log_ownership_proposed(owner_id: &AccountId, proposed_owner_id: &AccountId) {
+    log_event("ownership_proposed", json!({
+        "owner_id": owner_id,
+        "proposed_owner_id": proposed_owner_id,
+    }));
+}
+
+pub(crate) fn log_ownership_proposal_cancelled(proposed_owner_id: &AccountId) {
+    log_event("ownership_proposal_cancelled", json!({
+        "proposed_owner_id": proposed_owner_id,
+    }));
+}
+
+pub(crate) fn log_owner_changed(old_owner_id: &AccountId, new_owner_id: &AccountId) {
+    log_event("owner_changed", json!({
+        "old_owner_id": old_owner_id,
+        "new_owner_id": new_owner_id,
+    }));
+}
+
+pub(crate) fn log_role_granted(role: Role, account_id: &AccountId) {
+    log_event("role_granted", json!({
+        "role": role,
+        "account_id": account_id,
+    }));
+}
+
+pub(crate) fn log_role_revoked(role: Role, account_id: &AccountId) {
+    log_event("role_revoked", json!({
+        "role": role,
+        "account_id": account_id,
+    }));
+}
+
+pub(crate) fn log_pause_changed(jackpot_id: Option<JackpotId>, scope: &str, paused: bool) {
+    log_event("pause_changed", json!({
+        "jackpot_id": jackpot_id,
+        "scope": scope,
+        "paused": paused,
+    }));
+}
+
+pub(crate) fn log_wind_down_started(wind_down: &WindDown) {
+    log_event("wind_down_started", json!({
+        "refund_jackpot_id": wind_down.refund_jackpot_id,
+        "started_time": U64::from(wind_down.started_time),
+    }));
+}
+
+pub(crate) fn log_tickets_refunded(jackpot_id: JackpotId, ticket_ids: &[TicketId], amount: Balance, fee: Balance) {
+    log_event("tickets_refunded", json!({
+        "jackpot_id": jackpot_id,
+        "ticket_ids": ticket_ids,
+        "amount": U128::from(amount),
+        "fee": U128::from(fee),
+    }));
+}
+
+pub(crate) fn log_jackpot_remainder_released(jackpot_id: JackpotId, amount: Balance) {
+    log_event("jackpot_remainder_released", json!({
+        "jackpot_id": jackpot_id,
+        "amount": U128::from(amount),
+    }));
+}
+
+pub(crate) fn log_multisig_changed(multisig: &Multisig) {
+    log_event("multisig_changed", json!({
+        "approvers": multisig.approvers,
+        "threshold": multisig.threshold,
+        "proposal_duration": U64::from(multisig.proposal_duration),
+    }));
+}
+
+pub(crate) fn log_proposal_created(proposal: &Proposal) {
+    log_event("proposal_created", json!({
+        "proposal_id": proposal.id,
+        "proposer_id": proposal.proposer_id,
+        "action": proposal.action,
+        "deposit": U128::from(proposal.deposit),
+        "expiry_time": U64::from(proposal.expiry_time),
+    }));
+}
+
+pub(crate) fn log_proposal_approved(proposal_id: ProposalId, account_id: &AccountId) {
+    log_event("proposal_approved", json!({
+        "proposal_id": proposal_id,
+        "account_id": account_id,
+    }));
+}
+
+pub(crate) fn log_proposal_executed(proposal_id: ProposalId) {
+    log_event("proposal_executed", json!({
+        "proposal_id": proposal_id,
+    }));
+}
+
+pub(crate) fn log_proposal_removed(proposal_id: ProposalId) {
+    log_event("proposal_removed", json!({
+        "proposal_id": proposal_id,
+    }));
+}
+
+pub(crate) fn log_config_scheduled(queued_config: &QueuedConfig) {
+    log_event("config_scheduled", json!({
+        "ticket_price": U128::from(queued_config.config.ticket_price),
+        "claim_duration": U64::from(queued_config.config.claim_duration),
+        "game_type": queued_config.config.game_type,
+        "fee_bps": queued_config.config.fee_bps,
+        "effective_time": U64::from(queued_config.effective_time),
+    }));
+}
+
+pub(crate) fn log_config_cancelled(queued_config: &QueuedConfig) {
+    log_event("config_cancelled", json!({
+        "effective_time": U64::from(queued_config.effective_time),
+    }));
+}
+
+pub(crate) fn log_treasury_withdrawn(account_id: &AccountId, amount: Balance) {
+    log_event("treasury_withdrawn", json!({
+        "account_id": account_id,
+        "amount": U128::from(amount),
+    }));
+}
+
+pub(crate) fn log_upgrade_timelock_changed(upgrade_timelock: Timestamp, effective_time: Timestamp) {
+    log_event("upgrade_timelock_changed", json!({
+        "upgrade_timelock": U64::from(upgrade_timelock),
+        "effective_time": U64::from(effective_time),
+    }));
+}
+
+pub(crate) fn log_upgrade_staged(staged_upgrade: &StagedUpgrade) {
+    log_event("upgrade_staged", json!({
+        "code_hash": Base58CryptoHash::from(staged_upgrade.code_hash),
+        "deployable_time": U64::from(staged_upgrade.deployable_time),
+    }));
+}
+
+pub(crate) fn log_upgrade_cancelled(staged_upgrade: &StagedUpgrade) {
+    log_event("upgrade_cancelled", json!({
+        "code_hash": Base58CryptoHash::from(staged_upgrade.code_hash),
+    }));
+}
+
+pub(crate) fn log_upgrade_deployed(staged_upgrade: &StagedUpgrade) {
+    log_event("upgrade_deployed", json!({
+        "code_hash": Base58CryptoHash::from(staged_upgrade.code_hash),
+    }));
+}
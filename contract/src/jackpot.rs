@@ -2,6 +2,7 @@ use crate::*;
 
 pub enum JackpotStatus {
     Open,
+    Settling,
     Close
 }
 
@@ -12,14 +13,94 @@ impl Serialize for JackpotStatus {
     {
         match *self {
             JackpotStatus::Open => serializer.serialize_unit_variant("JackpotStatus", 0, "Open"),
-            JackpotStatus::Close => serializer.serialize_unit_variant("JackpotStatus", 1, "Close"),
+            JackpotStatus::Settling => serializer.serialize_unit_variant("JackpotStatus", 1, "Settling"),
+            JackpotStatus::Close => serializer.serialize_unit_variant("JackpotStatus", 2, "Close"),
         }
     }
 }
 
+// Declared when the jackpot is created, it can't be changed once tickets are sold
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum GameType {
+    Standard,
+    // The winning numbers are those of a ticket picked from the drawing seed
+    GuaranteedWinner,
+}
+
+impl Serialize for GameType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            GameType::Standard => serializer.serialize_unit_variant("GameType", 0, "Standard"),
+            GameType::GuaranteedWinner => serializer.serialize_unit_variant("GameType", 1, "GuaranteedWinner"),
+        }
+    }
+}
+
+pub struct WinTicket {
+    ticket_id: TicketId,
+    claimed: bool,
+}
+
+impl Serialize for WinTicket {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("WinTicket", 2)?;
+        state.serialize_field("ticketId", &self.ticket_id)?;
+        state.serialize_field("claimed", &self.claimed)?;
+        state.end()
+    }
+}
+
+// Lightweight view of a jackpot, without the ticket, winner and result lists
+pub struct JackpotSummary {
+    id: JackpotId,
+    game_type: GameType,
+    ticket_price: u128,
+    fee_bps: u32,
+    locked_amount: Balance,
+    prize_amount: Balance,
+    start_time: Timestamp,
+    end_time: Option<Timestamp>,
+    claim_deadline: Option<Timestamp>,
+    status: JackpotStatus,
+    is_refunded: bool,
+    pub no_of_tickets: u64,
+    no_of_win_tickets: u64,
+    no_of_drawed_results: u64,
+}
+
+impl Serialize for JackpotSummary {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("JackpotSummary", 14)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("gameType", &self.game_type)?;
+        state.serialize_field("ticketPrice", &self.ticket_price.to_string())?;
+        state.serialize_field("feeBps", &self.fee_bps)?;
+        state.serialize_field("lockedAmount", &self.locked_amount.to_string())?;
+        state.serialize_field("prizeAmount", &self.prize_amount.to_string())?;
+        state.serialize_field("startTime", &self.start_time)?;
+        state.serialize_field("endTime", &self.end_time)?;
+        state.serialize_field("claimDeadline", &self.claim_deadline)?;
+        state.serialize_field("status", &self.status)?;
+        state.serialize_field("isRefunded", &self.is_refunded)?;
+        state.serialize_field("noOfTickets", &self.no_of_tickets)?;
+        state.serialize_field("noOfWinTickets", &self.no_of_win_tickets)?;
+        state.serialize_field("noOfDrawedResults", &self.no_of_drawed_results)?;
+        state.end()
+    }
+}
+
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Jackpot {
-    id: JackpotId,
+    pub id: JackpotId,
     pub ticket_price: u128,
     pub locked_amount: Balance,
     pub ticket_ids: Vector<TicketId>,
@@ -28,24 +109,285 @@ pub struct Jackpot {
     start_time: Timestamp,
     pub end_time: Option<Timestamp>,
     created_time: Timestamp,
+    // How long winners have to claim their prize after the jackpot closes
+    pub claim_duration: Timestamp,
+    pub prize_amount: Balance,
+    pub claimed_ticket_ids: LookupSet<TicketId>,
+    // The same ids as `win_ticket_ids`, so a claim doesn't walk through all the winners
+    win_ticket_id_set: LookupSet<TicketId>,
+    // Index of the next ticket to check against the latest drawing result, set while settling
+    pub settle_index: Option<u64>,
+    // Ticket ids keyed by the bitmask of their picked numbers, each mask has its own vector so a
+    // settle batch only reads its part
+    tickets_by_numbers: LookupMap<u64, Vector<TicketId>>,
+    pub sales_paused: bool,
+    pub drawing_paused: bool,
+    pub game_type: GameType,
+    // Share of each ticket price paid into the treasury, in basis points
+    pub fee_bps: u32,
+    // Index of the next ticket to add to `tickets_by_numbers`, set while the tickets of a jackpot
+    // stored before the index are indexed
+    pub index_cursor: Option<u64>,
+    // Set while the collections are moved under the `StorageKey` prefixes
+    pub storage_move: Option<StorageMove>,
+    // Set when the lottery winds down before the jackpot is settled, its tickets are refunded
+    // and the winners found so far can't claim
+    pub is_refunded: bool,
+}
+
+// Vectors of a jackpot being moved under the `StorageKey` prefixes. The copies are filled while
+// the jackpot keeps reading its own vectors, then they are swapped and the elements left under
+// the legacy prefixes are removed.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct StorageMove {
+    // Position of the next element to copy
+    index: u64,
+    is_swapped: bool,
+    ticket_ids: Vector<TicketId>,
+    win_ticket_ids: Vector<TicketId>,
+    drawed_results: Vector<DrawingResult>,
+}
+
+impl StorageMove {
+    fn new(jackpot_id: JackpotId) -> Self {
+        Self {
+            index: 0,
+            is_swapped: false,
+            ticket_ids: Vector::new(StorageKey::JackpotTicketIds { jackpot_id }),
+            win_ticket_ids: Vector::new(StorageKey::JackpotWinTicketIds { jackpot_id }),
+            drawed_results: Vector::new(StorageKey::JackpotDrawedResults { jackpot_id }),
+        }
+    }
+}
+
+// Layout of jackpots stored by the first release, before prizes were claimed by the winners
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct JackpotV0 {
+    pub id: JackpotId,
+    pub ticket_price: u128,
+    pub locked_amount: Balance,
+    pub ticket_ids: Vector<TicketId>,
+    pub win_ticket_ids: Vector<TicketId>,
+    pub drawed_results: Vector<DrawingResult>,
+    pub start_time: Timestamp,
+    pub end_time: Option<Timestamp>,
+    pub created_time: Timestamp,
+}
+
+impl From<JackpotV0> for Jackpot {
+    fn from(jackpot: JackpotV0) -> Self {
+        // The first release paid the winners while drawing, so their prizes count as claimed
+        let mut claimed_ticket_ids = LookupSet::new(StorageKey::JackpotClaimedTicketIds { jackpot_id: jackpot.id });
+        let mut win_ticket_id_set = LookupSet::new(StorageKey::JackpotWinTicketIdSet { jackpot_id: jackpot.id });
+        for ticket_id in jackpot.win_ticket_ids.iter() {
+            claimed_ticket_ids.insert(&ticket_id);
+            win_ticket_id_set.insert(&ticket_id);
+        }
+
+        Self {
+            id: jackpot.id,
+            ticket_price: jackpot.ticket_price,
+            locked_amount: jackpot.locked_amount,
+            ticket_ids: jackpot.ticket_ids,
+            win_ticket_ids: jackpot.win_ticket_ids,
+            drawed_results: jackpot.drawed_results,
+            start_time: jackpot.start_time,
+            end_time: jackpot.end_time,
+            created_time: jackpot.created_time,
+            claim_duration: DEFAULT_CLAIM_DURATION,
+            prize_amount: 0,
+            claimed_ticket_ids,
+            win_ticket_id_set,
+            settle_index: Option::None,
+            tickets_by_numbers: LookupMap::new(StorageKey::JackpotTicketsByNumbers { jackpot_id: jackpot.id }),
+            sales_paused: false,
+            drawing_paused: false,
+            game_type: GameType::Standard,
+            fee_bps: 0,
+            index_cursor: Option::None,
+            storage_move: Option::None,
+            is_refunded: false,
+        }
+    }
 }
 
 impl Jackpot {
-    pub fn new(id: u32, start_time: Timestamp, ticket_price: u128, initialized_amount: u128) -> Self {
+    pub fn new(id: u32, start_time: Timestamp, ticket_price: u128, initialized_amount: u128, claim_duration: Timestamp, game_type: GameType, fee_bps: u32) -> Self {
         Self {
             id,
             ticket_price,
             locked_amount: initialized_amount,
-            ticket_ids: Vector::new(format!("tj{}", id).as_bytes()),
-            win_ticket_ids: Vector::new(format!("tjw{}", id).as_bytes()),
-            drawed_results: Vector::new(format!("dr{}", id).as_bytes()),
+            ticket_ids: Vector::new(StorageKey::JackpotTicketIds { jackpot_id: id }),
+            win_ticket_ids: Vector::new(StorageKey::JackpotWinTicketIds { jackpot_id: id }),
+            drawed_results: Vector::new(StorageKey::JackpotDrawedResults { jackpot_id: id }),
             start_time,
             end_time: Option::None,
             created_time: get_time_now(),
+            claim_duration,
+            prize_amount: 0,
+            claimed_ticket_ids: LookupSet::new(StorageKey::JackpotClaimedTicketIds { jackpot_id: id }),
+            win_ticket_id_set: LookupSet::new(StorageKey::JackpotWinTicketIdSet { jackpot_id: id }),
+            settle_index: Option::None,
+            tickets_by_numbers: LookupMap::new(StorageKey::JackpotTicketsByNumbers { jackpot_id: id }),
+            sales_paused: false,
+            drawing_paused: false,
+            game_type,
+            fee_bps,
+            index_cursor: Option::None,
+            storage_move: Option::None,
+            is_refunded: false,
+        }
+    }
+
+    pub fn get_claim_deadline(&self) -> Option<Timestamp> {
+        self.end_time.map(|t| t + self.claim_duration)
+    }
+
+    /// Part of the ticket price paid into the treasury, the rest is locked in the jackpot.
+    pub fn get_ticket_fee(&self) -> Balance {
+        house_fee(self.ticket_price, self.fee_bps)
+    }
+
+    pub fn is_claim_expired(&self) -> bool {
+        match self.get_claim_deadline() {
+            None => false,
+            Some(deadline) => get_time_now() > deadline,
+        }
+    }
+
+    pub fn is_win_ticket(&self, ticket_id: &TicketId) -> bool {
+        !self.is_refunded && self.win_ticket_id_set.contains(ticket_id)
+    }
+
+    pub fn add_win_ticket(&mut self, ticket_id: &TicketId) {
+        self.win_ticket_ids.push(ticket_id);
+        self.win_ticket_id_set.insert(ticket_id);
+    }
+
+    pub fn index_ticket(&mut self, ticket_id: &TicketId, picked_mask: u64) {
+        let mut ticket_ids = self.get_ticket_ids_by_mask(picked_mask);
+        ticket_ids.push(ticket_id);
+        self.tickets_by_numbers.insert(&picked_mask, &ticket_ids);
+    }
+
+    /// Index the next `limit` tickets, returns true once every ticket is indexed.
+    pub(crate) fn index_next_tickets(&mut self, tickets: &UnorderedMap<TicketId, Ticket>, limit: u64) -> bool {
+        let from_index = match self.index_cursor {
+            Some(index) => index,
+            None => return true,
+        };
+
+        let to_index = std::cmp::min(from_index + limit, self.ticket_ids.len());
+        for i in from_index..to_index {
+            let ticket_id = self.ticket_ids.get(i).unwrap();
+            let ticket = tickets.get(&ticket_id).unwrap();
+            self.index_ticket(&ticket_id, ticket.picked_mask);
+        }
+
+        let is_indexed = to_index >= self.ticket_ids.len();
+        self.index_cursor = if is_indexed { None } else { Some(to_index) };
+        is_indexed
+    }
+
+    pub fn get_ticket_ids_by_mask(&self, mask: u64) -> Vector<TicketId> {
+        self.tickets_by_numbers.get(&mask)
+            .unwrap_or_else(|| Vector::new(StorageKey::TicketsByNumbers { jackpot_id: self.id, mask }))
+    }
+
+    pub fn set_claimed(&mut self, ticket_id: &TicketId) {
+        self.claimed_ticket_ids.insert(ticket_id);
+    }
+
+    /// Move the next `limit` elements of the vectors stored under the ASCII prefixes used by the
+    /// first release. Returns true once every vector is under its `StorageKey` prefix.
+    pub(crate) fn move_to_storage_keys(&mut self, limit: u64) -> bool {
+        let jackpot_id = self.id;
+        let mut storage_move = match self.storage_move.take() {
+            Some(storage_move) => storage_move,
+            None if has_prefix(&self.ticket_ids, StorageKey::JackpotTicketIds { jackpot_id }) => return true,
+            None => StorageMove::new(jackpot_id),
+        };
+
+        let no_of_tickets = self.ticket_ids.len();
+        let no_of_win_tickets = self.win_ticket_ids.len();
+        let no_of_elements = no_of_tickets + no_of_win_tickets + self.drawed_results.len();
+        let mut remaining = limit;
+
+        // Copy the elements, the jackpot keeps reading its own vectors until all of them are there
+        while !storage_move.is_swapped && remaining > 0 && storage_move.index < no_of_elements {
+            let i = storage_move.index;
+            if i < no_of_tickets {
+                storage_move.ticket_ids.push_raw(&self.ticket_ids.get_raw(i).unwrap());
+            }
+            else if i < no_of_tickets + no_of_win_tickets {
+                storage_move.win_ticket_ids.push_raw(&self.win_ticket_ids.get_raw(i - no_of_tickets).unwrap());
+            }
+            else {
+                storage_move.drawed_results.push_raw(&self.drawed_results.get_raw(i - no_of_tickets - no_of_win_tickets).unwrap());
+            }
+
+            storage_move.index += 1;
+            remaining -= 1;
+        }
+
+        if !storage_move.is_swapped && storage_move.index >= no_of_elements {
+            std::mem::swap(&mut self.ticket_ids, &mut storage_move.ticket_ids);
+            std::mem::swap(&mut self.win_ticket_ids, &mut storage_move.win_ticket_ids);
+            std::mem::swap(&mut self.drawed_results, &mut storage_move.drawed_results);
+            storage_move.is_swapped = true;
+        }
+
+        // Then remove the elements left under the legacy prefixes
+        while storage_move.is_swapped && remaining > 0 {
+            if storage_move.ticket_ids.pop_raw().is_none()
+                && storage_move.win_ticket_ids.pop_raw().is_none()
+                && storage_move.drawed_results.pop_raw().is_none() {
+                return true;
+            }
+
+            remaining -= 1;
         }
+
+        self.storage_move = Some(storage_move);
+        false
+    }
+
+    pub fn get_summary(&self) -> JackpotSummary {
+        JackpotSummary {
+            id: self.id,
+            game_type: self.game_type,
+            ticket_price: self.ticket_price,
+            fee_bps: self.fee_bps,
+            locked_amount: self.locked_amount,
+            prize_amount: self.prize_amount,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            claim_deadline: self.get_claim_deadline(),
+            status: self.get_status(),
+            is_refunded: self.is_refunded,
+            no_of_tickets: self.ticket_ids.len(),
+            no_of_win_tickets: self.win_ticket_ids.len(),
+            no_of_drawed_results: self.drawed_results.len(),
+        }
+    }
+
+    pub fn get_win_tickets_page(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<WinTicket> {
+        get_page_range(self.win_ticket_ids.len(), from_index, limit)
+            .map(|i| {
+                let ticket_id = self.win_ticket_ids.get(i).unwrap();
+                WinTicket {
+                    ticket_id,
+                    claimed: self.claimed_ticket_ids.contains(&ticket_id),
+                }
+            })
+            .collect()
     }
 
     pub fn get_status(&self) -> JackpotStatus {
+        if self.settle_index.is_some() {
+            return JackpotStatus::Settling;
+        }
+
         let now = get_time_now();
         match self.end_time {
             None => {
@@ -73,19 +415,28 @@ impl Serialize for Jackpot {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Jackpot", 1)?;
+        let mut state = serializer.serialize_struct("Jackpot", 19)?;
         state.serialize_field("id", &self.id)?;
+        state.serialize_field("gameType", &self.game_type)?;
         state.serialize_field("ticketPrice", &self.ticket_price.to_string())?;
+        state.serialize_field("feeBps", &self.fee_bps)?;
         state.serialize_field("lockedAmount", &self.locked_amount.to_string())?;
         state.serialize_field("startTime", &self.start_time)?;
         state.serialize_field("createdTime", &self.created_time)?;
         state.serialize_field("endTime", &self.end_time)?;
         state.serialize_field("status", &self.get_status())?;
+        state.serialize_field("settleIndex", &self.settle_index)?;
+        state.serialize_field("claimDuration", &self.claim_duration)?;
+        state.serialize_field("claimDeadline", &self.get_claim_deadline())?;
+        state.serialize_field("prizeAmount", &self.prize_amount.to_string())?;
+        state.serialize_field("salesPaused", &self.sales_paused)?;
+        state.serialize_field("drawingPaused", &self.drawing_paused)?;
+        state.serialize_field("isRefunded", &self.is_refunded)?;
         
+        // The collections are read page by page with the jackpot views
         state.serialize_field("noOfTickets", &self.ticket_ids.len())?;
-        state.serialize_field("ticketIds", &self.ticket_ids.to_vec())?;
-        state.serialize_field("winTicketIds", &self.win_ticket_ids.to_vec())?;
-        state.serialize_field("drawedResults", &self.drawed_results.to_vec())?;
+        state.serialize_field("noOfWinTickets", &self.win_ticket_ids.len())?;
+        state.serialize_field("noOfDrawedResults", &self.drawed_results.len())?;
 
         state.end()
     }
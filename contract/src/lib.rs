@@ -13,9 +13,10 @@
 
 // To conserve gas, efficient serialization is achieved through Borsh (http://borsh.io/)
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::{env, near_bindgen, setup_alloc, AccountId, Balance, Promise, Timestamp};
-use near_sdk::collections::{UnorderedMap, Vector};
-use near_sdk::json_types::{U128};
+use near_sdk::{env, near_bindgen, setup_alloc, AccountId, Balance, BorshStorageKey, CryptoHash, Gas, Promise, StorageUsage, Timestamp};
+use near_sdk::collections::{LookupMap, LookupSet, UnorderedMap, Vector};
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8, U64, U128};
+use serde::Deserialize;
 use serde::ser::{Serialize, Serializer, SerializeStruct};
 use std::option::Option;
 use std::convert::TryFrom;
@@ -23,72 +24,146 @@ use std::convert::TryFrom;
 setup_alloc!();
 
 const ONE_NEAR: u128 = 1_000_000_000_000_000_000_000_000;
-const MAX_DRAWING_NUMBER: u8 = 55;
+// 30 days in nanoseconds
+const DEFAULT_CLAIM_DURATION: Timestamp = 30 * 24 * 60 * 60 * 1_000_000_000;
+const DEFAULT_SETTLE_LIMIT: u64 = 500;
+const DEFAULT_PAGE_LIMIT: u64 = 50;
+const MAX_PAGE_LIMIT: u64 = 100;
+// 2 days in nanoseconds
+const DEFAULT_UPGRADE_TIMELOCK: Timestamp = 2 * 24 * 60 * 60 * 1_000_000_000;
 
 type TicketId = u64;
 type JackpotId = u32;
+type ProposalId = u64;
 
+use lottery_core::*;
 use crate::utils::*;
+use crate::runtime::*;
+pub use crate::migration::*;
+pub use crate::upgrade::*;
+pub use crate::storage::*;
+pub use crate::roles::*;
+pub use crate::wind_down::*;
+pub use crate::multisig::*;
+pub use crate::config::*;
+pub use crate::treasury::*;
+pub use crate::audit::*;
+use crate::events::*;
 pub use crate::account::*;
 pub use crate::ticket::*;
 pub use crate::jackpot::*;
 
 mod utils;
+mod runtime;
+mod migration;
+mod upgrade;
+mod storage;
+mod roles;
+mod ownership;
+mod pause;
+mod wind_down;
+mod multisig;
+mod config;
+mod treasury;
+mod audit;
+mod events;
 mod account;
 mod ticket;
 mod jackpot;
 
+// Prefixes of the persistent collections. The Borsh encoding starts with the variant index, so
+// they can't collide with each other or with the ASCII prefixes used before them.
+#[derive(BorshStorageKey, BorshSerialize)]
+pub(crate) enum StorageKey {
+    AccountInfoes,
+    Jackpots,
+    Tickets,
+    // Account ids are hashed, a crafted id cannot shape the prefix
+    AccountTicketIds { account_hash: CryptoHash },
+    JackpotTicketIds { jackpot_id: JackpotId },
+    JackpotWinTicketIds { jackpot_id: JackpotId },
+    JackpotDrawedResults { jackpot_id: JackpotId },
+    JackpotClaimedTicketIds { jackpot_id: JackpotId },
+    JackpotTicketsByNumbers { jackpot_id: JackpotId },
+    Roles,
+    Proposals,
+    AuditLog,
+    TicketsByNumbers { jackpot_id: JackpotId, mask: u64 },
+    JackpotWinTicketIdSet { jackpot_id: JackpotId },
+}
+
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct DrawingResult {
+    drawed_numbers: [u8; 6],
+    created_time: Timestamp,
+    drawed_mask: u64,
+    // Seed material, the numbers can be re-derived from it with the lottery core
+    seed: u64,
+    no_of_tickets: u64,
+    lucky_ticket_index: Option<u64>,
+    // Rules the result was drawn with, the verifier picks the lucky ticket accordingly
+    version: u8,
+}
+
+// Layout of drawing results stored by the first release
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct DrawingResultV0 {
     drawed_numbers: [u8; 6],
     created_time: Timestamp
 }
 
+impl From<DrawingResultV0> for DrawingResult {
+    fn from(result: DrawingResultV0) -> Self {
+        Self {
+            drawed_numbers: result.drawed_numbers,
+            created_time: result.created_time,
+            drawed_mask: numbers_to_bitmask(&result.drawed_numbers),
+            seed: result.created_time,
+            no_of_tickets: 0,
+            lucky_ticket_index: Option::None,
+            version: 0,
+        }
+    }
+}
+
 impl Serialize for DrawingResult {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("DrawingResult", 1)?;
+        let mut state = serializer.serialize_struct("DrawingResult", 7)?;
         state.serialize_field("drawedNumbers", &self.drawed_numbers)?;
+        state.serialize_field("drawedMask", &U64::from(self.drawed_mask))?;
         state.serialize_field("createdTime", &self.created_time)?;
+        state.serialize_field("seed", &U64::from(self.seed))?;
+        state.serialize_field("noOfTickets", &self.no_of_tickets)?;
+        state.serialize_field("luckyTicketIndex", &self.lucky_ticket_index)?;
+        state.serialize_field("version", &self.version)?;
         state.end()
     }
 }
 
-impl Default for DrawingResult {
-    fn default() -> Self {
-        let mut drawed_numbers: [u8; 6] = [0, 0, 0, 0, 0, 0];
-        let mut ran_no: u8 = 1;
-
-        for i in 0..6 {
-            loop {
-                ran_no += 1;
-                let number = u8::try_from(get_random_number(MAX_DRAWING_NUMBER.into(), ran_no.into())).ok().unwrap();
-                let mut j = 0;
-                while j < i {
-                    if drawed_numbers[j] == number {
-                        break;
-                    }
-                    j += 1;
-                }
-
-                if j >= i {
-                    drawed_numbers[i] = number;
-                    break;
-                }
-            }
-        }
-
-        drawed_numbers.sort();
-
+impl From<Draw> for DrawingResult {
+    fn from(draw: Draw) -> Self {
         Self {
-            drawed_numbers,
-            created_time: get_time_now(),
+            drawed_numbers: draw.drawed_numbers,
+            created_time: draw.created_time,
+            drawed_mask: draw.drawed_mask,
+            seed: draw.seed,
+            no_of_tickets: draw.no_of_tickets,
+            lucky_ticket_index: draw.lucky_ticket_index,
+            version: draw.version,
         }
     }
 }
 
+impl Default for DrawingResult {
+    fn default() -> Self {
+        let no_tickets: &[[u8; 6]] = &[];
+        Self::from(draw(&NearRuntime, &NearRuntime, no_tickets, false))
+    }
+}
+
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Lottery {
@@ -97,11 +172,40 @@ pub struct Lottery {
     account_infoes: UnorderedMap<AccountId, AccountInfo>,
     jackpots: Vector<Jackpot>,
     tickets: UnorderedMap<TicketId, Ticket>,
+    // Index of the oldest jackpot whose unclaimed prizes have not been rolled over yet
+    rollover_index: u64,
+    // Time between staging an upgrade and deploying it
+    upgrade_timelock: Timestamp,
+    staged_upgrade: Option<StagedUpgrade>,
+    // Storage used by registering an account, its cost is the minimum storage deposit
+    account_storage_usage: StorageUsage,
+    // Roles granted to other accounts than the owner
+    roles: UnorderedMap<AccountId, Vec<Role>>,
+    // Account which has to accept the ownership before it is transferred
+    proposed_owner_id: Option<AccountId>,
+    // Stops everything but the withdrawals
+    paused: bool,
+    // Set for good once the lottery is stopped
+    wind_down: Option<WindDown>,
+    // Approvers of the privileged actions, once set up
+    multisig: Option<Multisig>,
+    proposals: UnorderedMap<ProposalId, Proposal>,
+    proposal_count: u64,
+    // Parameters of the next jackpots, and their queued change
+    config: JackpotConfig,
+    queued_config: Option<QueuedConfig>,
+    // Append-only history of the privileged actions
+    audit_log: Vector<AuditEntry>,
+    // Fees taken from the ticket sales, owned by the house
+    treasury: Balance,
+    // Funds held for the players, the jackpots and the proposals
+    fund_totals: FundTotals,
+    queued_upgrade_timelock: Option<QueuedUpgradeTimelock>,
 }
 
 impl Default for Lottery {
     fn default() -> Self {
-        panic!("Should be initialized before usage.");
+        panic!("{}", LotteryError::NotInitialized);
     }
 }
 
@@ -109,17 +213,38 @@ impl Default for Lottery {
 impl Lottery {
     #[init]
     pub fn new(owner_id: AccountId) -> Self {
-        assert!(env::is_valid_account_id(&owner_id.as_bytes()), "Invalid owner account!");
-        assert!(!env::state_exists(), "Already initialized!");
+        assert!(env::is_valid_account_id(owner_id.as_bytes()), "{}", LotteryError::InvalidAccountId);
+        assert!(!env::state_exists(), "{}", LotteryError::AlreadyInitialized);
 
         env::log(format!("Creating a Lottery with owner id '{}'", &owner_id).as_bytes());
 
-        Self {
+        let mut lottery = Self {
             owner_id,
-            account_infoes: UnorderedMap::new(b"account_info".to_vec()),
-            jackpots: Vector::new(b"jackpot".to_vec()),
-            tickets: UnorderedMap::new(b"ticket".to_vec()),
-        }
+            account_infoes: UnorderedMap::new(StorageKey::AccountInfoes),
+            jackpots: Vector::new(StorageKey::Jackpots),
+            tickets: UnorderedMap::new(StorageKey::Tickets),
+            rollover_index: 0,
+            upgrade_timelock: DEFAULT_UPGRADE_TIMELOCK,
+            staged_upgrade: Option::None,
+            account_storage_usage: 0,
+            roles: UnorderedMap::new(StorageKey::Roles),
+            proposed_owner_id: Option::None,
+            paused: false,
+            wind_down: Option::None,
+            multisig: Option::None,
+            proposals: UnorderedMap::new(StorageKey::Proposals),
+            proposal_count: 0,
+            config: JackpotConfig::default(),
+            queued_config: Option::None,
+            audit_log: Vector::new(StorageKey::AuditLog),
+            treasury: 0,
+            fund_totals: FundTotals::default(),
+            queued_upgrade_timelock: Option::None,
+        };
+        lottery.measure_account_storage_usage();
+        VersionedLottery::write_version();
+
+        lottery
     }
 
     pub fn has_initialized(&self) -> bool {
@@ -132,69 +257,86 @@ impl Lottery {
         number
     }
     
-    pub fn set_owner_id(&mut self, owner_id: AccountId) {
-        let current_owner_id = env::signer_account_id();
-        assert_eq!(current_owner_id, self.owner_id, "Only owner can change ownership.");
-
-        self.owner_id = owner_id;
-    }
-
     pub fn get_owner_id(&self) -> AccountId {
         self.owner_id.clone()
     }
 
     pub fn get_account_info_or_default(&self, account_id: &AccountId) -> AccountInfo {
-        match self.account_infoes.get(&account_id) {
-            None => AccountInfo::new((&account_id).to_string()),
+        match self.account_infoes.get(account_id) {
+            None => AccountInfo::new(account_id.to_string()),
             Some(a) => a,
         }
     }
 
     pub fn get_account_balance(&self, account_id: &AccountId) -> U128 {
-        self.get_account_info_or_default(&account_id).balance.into()
+        self.get_account_info_or_default(account_id).balance.into()
     }
 
-    pub fn get_account_tickets(&self, account_id: &AccountId) -> Vec<Ticket> {
-        let ticket_ids = self.get_account_info_or_default(&account_id).ticket_ids.to_vec();
-        let mut tickets = Vec::new();
+    pub fn get_account_tickets_paged(&self, account_id: &AccountId, from_index: Option<u64>, limit: Option<u64>) -> Vec<Ticket> {
+        let ticket_ids = self.get_account_info_or_default(account_id).ticket_ids;
 
-        for i in 0..ticket_ids.len() {
-            let ticket_id = ticket_ids.get(i).unwrap();
-            let ticket = self.tickets.get(ticket_id).unwrap();
-            tickets.push(ticket);
-        }
+        get_page_range(ticket_ids.len(), from_index, limit)
+            .map(|i| self.tickets.get(&ticket_ids.get(i).unwrap()).unwrap())
+            .collect()
+    }
 
-        tickets
+    pub fn get_account_ids(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<AccountId> {
+        let account_ids = self.account_infoes.keys_as_vector();
+
+        get_page_range(account_ids.len(), from_index, limit)
+            .map(|i| account_ids.get(i).unwrap())
+            .collect()
+    }
+
+    pub fn get_ticket(&self, ticket_id: TicketId) -> Option<Ticket> {
+        self.tickets.get(&ticket_id)
+    }
+
+    pub fn get_tickets(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<Ticket> {
+        let tickets = self.tickets.values_as_vector();
+
+        get_page_range(tickets.len(), from_index, limit)
+            .map(|i| tickets.get(i).unwrap())
+            .collect()
     }
 
     #[payable]
     pub fn deposit(&mut self) {
-        let account_id = env::signer_account_id();
+        self.assert_not_paused();
+        self.assert_not_winding_down();
+
+        let account_id = env::predecessor_account_id();
         let deposit_amount = env::attached_deposit();
 
-        let mut account_info = self.get_account_info_or_default(&account_id);
+        let mut account_info = self.get_registered_account_info(&account_id);
         account_info.balance += deposit_amount;
         self.account_infoes.insert(&account_id, &account_info);
+        self.fund_totals.player_balance += deposit_amount;
+
+        log_deposit(&account_id, deposit_amount);
     }
     
     pub fn withdraw(&mut self) {
-        let account_id = env::signer_account_id();
+        let account_id = env::predecessor_account_id();
 
         // Get account balance
         let mut account_info = self.get_account_info_or_default(&account_id);
         let proceeds = account_info.balance;
-        assert!(proceeds > 0, "nothing to withdraw");
+        assert!(proceeds > 0, "{}", LotteryError::NothingToWithdraw);
 
         // Reset account balance
         account_info.balance = 0;
         self.account_infoes.insert(&account_id, &account_info);
+        self.fund_totals.player_balance -= proceeds;
+
+        log_withdraw(&account_id, proceeds);
 
         // Process withdrawal
         Promise::new(account_id).transfer(proceeds);
     }
 
     fn generate_ticket_id(&self) -> TicketId {
-        (self.tickets.len() + 1).into()
+        self.tickets.len() + 1
     }
 
     fn generate_jackpot_id(&self) -> JackpotId {
@@ -211,162 +353,295 @@ impl Lottery {
         }
     } 
 
-    pub fn get_jackpots(&self) -> Vec<Jackpot> {
-        self.jackpots.to_vec()
+    /// The tickets, winners and results of the jackpot are read page by page with the views below.
+    pub fn get_jackpot(&self, jackpot_id: JackpotId) -> Option<JackpotSummary> {
+        self.internal_get_jackpot(jackpot_id).map(|jackpot| jackpot.get_summary())
+    }
+
+    pub fn get_jackpot_summaries(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<JackpotSummary> {
+        get_page_range(self.jackpots.len(), from_index, limit)
+            .map(|i| self.jackpots.get(i).unwrap().get_summary())
+            .collect()
+    }
+
+    pub fn get_jackpot_ticket_ids(&self, jackpot_id: JackpotId, from_index: Option<u64>, limit: Option<u64>) -> Vec<TicketId> {
+        let jackpot = self.internal_get_jackpot(jackpot_id).unwrap_or_else(|| panic!("{}", LotteryError::JackpotNotFound));
+
+        get_page_range(jackpot.ticket_ids.len(), from_index, limit)
+            .map(|i| jackpot.ticket_ids.get(i).unwrap())
+            .collect()
+    }
+
+    pub fn get_jackpot_win_tickets(&self, jackpot_id: JackpotId, from_index: Option<u64>, limit: Option<u64>) -> Vec<WinTicket> {
+        let jackpot = self.internal_get_jackpot(jackpot_id).unwrap_or_else(|| panic!("{}", LotteryError::JackpotNotFound));
+
+        jackpot.get_win_tickets_page(from_index, limit)
+    }
+
+    pub fn get_jackpot_drawed_results(&self, jackpot_id: JackpotId, from_index: Option<u64>, limit: Option<u64>) -> Vec<DrawingResult> {
+        let jackpot = self.internal_get_jackpot(jackpot_id).unwrap_or_else(|| panic!("{}", LotteryError::JackpotNotFound));
+
+        get_page_range(jackpot.drawed_results.len(), from_index, limit)
+            .map(|i| jackpot.drawed_results.get(i).unwrap())
+            .collect()
+    }
+
+    fn internal_get_jackpot(&self, jackpot_id: JackpotId) -> Option<Jackpot> {
+        jackpot_id.checked_sub(1).and_then(|index| self.jackpots.get(index.into()))
     }
 
     fn update_latest_jackpot(&mut self, jackpot: &Jackpot) {
         self.jackpots.pop();
-        self.jackpots.push(&jackpot);
+        self.jackpots.push(jackpot);
+    }
+
+    fn update_jackpot(&mut self, jackpot: &Jackpot) {
+        self.jackpots.replace((jackpot.id - 1).into(), jackpot);
+    }
+
+    // Move the unclaimed prizes of expired jackpots into the given jackpot's pot
+    fn rollover_expired_prizes(&mut self, jackpot: &mut Jackpot) {
+        while self.rollover_index < self.jackpots.len() {
+            let mut expired_jackpot = self.jackpots.get(self.rollover_index).unwrap();
+
+            // Jackpots close in order, so stop at the first one which can still be claimed
+            if expired_jackpot.id == jackpot.id || !expired_jackpot.is_claim_expired() {
+                break;
+            }
+
+            log_prize_rolled_over(expired_jackpot.id, jackpot.id, expired_jackpot.locked_amount);
+
+            jackpot.locked_amount += expired_jackpot.locked_amount;
+            expired_jackpot.locked_amount = 0;
+            self.jackpots.replace(self.rollover_index, &expired_jackpot);
+
+            self.rollover_index += 1;
+        }
     }
 
+    /// The jackpot gets the parameters in force, see `schedule_config`. A seeded jackpot has to be
+    /// proposed once the multisig is set up.
     #[payable]
-    pub fn create_jackpot(&mut self, ticket_price: Option<u128>) {
-        // Check account right (The signer must be the contract owner)
-        let account_id = env::signer_account_id();
-        assert!(account_id == self.owner_id, "The signer must be the contract owner.");
+    pub fn create_jackpot(&mut self) {
+        // Check account right (The caller must be an operator)
+        self.assert_role(Role::Operator);
+
+        let initialized_amount = env::attached_deposit();
+        if initialized_amount > 0 {
+            self.assert_no_multisig();
+        }
+
+        self.internal_create_jackpot(initialized_amount);
+    }
+
+    fn internal_create_jackpot(&mut self, initialized_amount: Balance) {
+        self.assert_not_paused();
+        self.assert_not_winding_down();
 
         // Check current jackpot status
         let latest_jackpot = self.get_latest_jackpot();
         match latest_jackpot {
             None => (),
             Some(j) => {
-                assert!(matches!(j.get_status(), JackpotStatus::Close), "The latest jackpot is still open. Cannot create a new one!"); 
+                assert!(matches!(j.get_status(), JackpotStatus::Close), "{}", LotteryError::JackpotStillOpen);
             }
         }
 
-        let ticket_price = ticket_price.unwrap_or(ONE_NEAR);
-        let initialized_amount = env::attached_deposit();
-        
+        // The jackpot keeps the parameters in force now, later changes don't apply to it
+        self.apply_queued_config();
+        let config = self.config.clone();
+
         // Create a new jackpot
         let id = self.generate_jackpot_id();
         let start_time = get_time_now();
-        let jackpot = Jackpot::new(id, start_time, ticket_price, initialized_amount);
+        let mut jackpot = Jackpot::new(id, start_time, config.ticket_price, initialized_amount, config.claim_duration, config.game_type, config.fee_bps);
+        log_jackpot_created(&jackpot);
+        self.record_audit(AuditAction::JackpotCreated { jackpot_id: id, initialized_amount });
+
+        self.rollover_expired_prizes(&mut jackpot);
 
         self.jackpots.push(&jackpot);
+        self.fund_totals.locked_amount += initialized_amount;
     }
 
     pub fn buy_ticket(&mut self, picked_numbers: [u8; 6]) {
-        let account_id = env::signer_account_id();
-        let mut account_info = self.get_account_info_or_default(&account_id);
+        self.assert_not_paused();
+        self.assert_not_winding_down();
+
+        let initial_storage_usage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        let mut account_info = self.get_registered_account_info(&account_id);
 
         // Check the current Jackpot is available for buying tickets
         let latest_jackpot = self.get_latest_jackpot();
-        assert!(latest_jackpot.is_some() && matches!(latest_jackpot.as_ref().unwrap().get_status(), JackpotStatus::Open), "There is no open jackpot.");
+        assert!(latest_jackpot.is_some() && matches!(latest_jackpot.as_ref().unwrap().get_status(), JackpotStatus::Open), "{}", LotteryError::NoOpenJackpot);
         
         // Check user balance must be enough to by a ticket
         let mut latest_jackpot = latest_jackpot.unwrap();
+        assert!(!latest_jackpot.sales_paused, "{}", LotteryError::SalesPaused);
+        assert!(latest_jackpot.index_cursor.is_none(), "{}", LotteryError::TicketsNotIndexed);
+        assert!(latest_jackpot.storage_move.is_none(), "{}", LotteryError::JackpotStorageMoving);
         let ticket_price = latest_jackpot.ticket_price;
-        assert!(account_info.balance >= ticket_price, "No balance to buy ticket!!!");
+        assert!(account_info.balance >= ticket_price, "{}", LotteryError::InsufficientBalance);
 
         // Create a ticket and add to list
         let ticket_id = self.generate_ticket_id();
 
         // Sort & validate numbers
-        let mut picked_numbers = picked_numbers;
-        picked_numbers.sort();
-
-        for i in 0..6 {
-            if picked_numbers[i] < 1 || picked_numbers[i] > 55 {
-                panic!("The chosen number must be between 1 and 55.");
-            }
+        let picked_numbers = match validate_numbers(&picked_numbers) {
+            Ok(numbers) => numbers,
+            Err(e) => panic!("{}", e),
+        };
 
-            if i == 0 {
-                continue;
-            }
-
-            assert!(picked_numbers[i-1] < picked_numbers[i], "The chosen numbers cannot be duplicated.");
-        }
-
-        let ticket = Ticket::new(&ticket_id, &account_id, &picked_numbers);
+        let ticket = Ticket::new(&ticket_id, &account_id, &picked_numbers, latest_jackpot.id);
         self.tickets.insert(&ticket_id, &ticket);
 
         // Add the new ticket to current Jackpot
         latest_jackpot.ticket_ids.push(&ticket_id);
+        latest_jackpot.index_ticket(&ticket_id, ticket.picked_mask);
 
-        // Descrease account balance and increase locked balance
+        // Descrease account balance and increase locked balance, the fee goes to the treasury
+        let fee = latest_jackpot.get_ticket_fee();
         account_info.balance -= ticket_price;
-        latest_jackpot.locked_amount += ticket_price;
+        latest_jackpot.locked_amount += ticket_price - fee;
+        self.fund_totals.player_balance -= ticket_price;
+        self.fund_totals.locked_amount += ticket_price - fee;
+        self.treasury += fee;
 
         // Add ticket to current account
         account_info.ticket_ids.push(&ticket_id);
 
+        self.update_latest_jackpot(&latest_jackpot);
+
+        // The player pays for the storage of the ticket
+        account_info.charge_storage(initial_storage_usage);
         self.account_infoes.insert(&account_id, &account_info);
 
-        self.update_latest_jackpot(&latest_jackpot);
+        log_ticket_bought(&ticket, ticket_price, fee);
     }
 
-    pub fn draw_jackpot(&mut self, force_win: bool) -> bool{
-        // Check account right (The signer must be the contract owner)
-        let account_id = env::signer_account_id();
-        assert!(account_id == self.owner_id, "The signer must be the contract owner.");
+    /// Draw the open jackpot, a guaranteed-winner jackpot takes the numbers of one of its tickets.
+    pub fn draw_jackpot(&mut self) {
+        // Check account right (The caller must be a drawer)
+        self.assert_role(Role::Drawer);
+        self.assert_not_paused();
+        self.assert_not_winding_down();
 
         // Check current jackpot status
         let latest_jackpot = self.get_latest_jackpot();
-        match latest_jackpot {
-            None => (),
-            Some(ref j) => {
-                assert!(matches!(j.get_status(), JackpotStatus::Open), "The latest jackpot is closed. Cannot draw anymore!"); 
-            }
-        }
+        assert!(latest_jackpot.is_some() && matches!(latest_jackpot.as_ref().unwrap().get_status(), JackpotStatus::Open), "{}", LotteryError::NoOpenJackpot);
 
         let mut latest_jackpot = latest_jackpot.unwrap();
+        assert!(!latest_jackpot.drawing_paused, "{}", LotteryError::DrawingPaused);
+        assert!(latest_jackpot.index_cursor.is_none(), "{}", LotteryError::TicketsNotIndexed);
+        assert!(latest_jackpot.storage_move.is_none(), "{}", LotteryError::JackpotStorageMoving);
 
-        let mut result = DrawingResult::default();
-
-        if force_win {
-            let no_of_tickets = latest_jackpot.ticket_ids.len();
-            let lucky_ticket_index = get_random_number(no_of_tickets, 0) - 1;
-            let lucky_ticket_id = latest_jackpot.ticket_ids.get(lucky_ticket_index).unwrap();
-            let lucky_ticket = self.tickets.get(&lucky_ticket_id).unwrap();
+        self.rollover_expired_prizes(&mut latest_jackpot);
 
-            result.drawed_numbers = lucky_ticket.picked_numbers.clone();
-        }
+        let storage = JackpotTickets { ticket_ids: &latest_jackpot.ticket_ids, tickets: &self.tickets };
+        let guaranteed_winner = latest_jackpot.game_type == GameType::GuaranteedWinner;
+        let result = DrawingResult::from(draw(&NearRuntime, &NearRuntime, &storage, guaranteed_winner));
 
-        // Add new result to list
+        // Add new result to list, the tickets are checked against it by settle_jackpot
         latest_jackpot.drawed_results.push(&result);
+        latest_jackpot.settle_index = Some(0);
+
+        self.update_latest_jackpot(&latest_jackpot);
+
+        log_draw_result(latest_jackpot.id, &result, latest_jackpot.game_type);
+        self.record_audit(AuditAction::JackpotDrawn { jackpot_id: latest_jackpot.id, game_type: latest_jackpot.game_type });
+    }
+
+    /// Check the next `limit` tickets matching the latest drawing result.
+    /// Returns true once every ticket has been checked.
+    pub fn settle_jackpot(&mut self, limit: Option<u64>) -> bool {
+        // Check account right (The caller must be a drawer)
+        self.assert_role(Role::Drawer);
+        self.assert_not_paused();
+        self.assert_not_winding_down();
+
+        // Check current jackpot status
+        let latest_jackpot = self.get_latest_jackpot();
+        assert!(latest_jackpot.is_some() && matches!(latest_jackpot.as_ref().unwrap().get_status(), JackpotStatus::Settling), "{}", LotteryError::JackpotNotSettling);
 
-        // Check result
-        let no_of_tickets = latest_jackpot.ticket_ids.len();
-        for i in 0..no_of_tickets {
-            let ticket_id = latest_jackpot.ticket_ids.get(i).unwrap();
+        let mut latest_jackpot = latest_jackpot.unwrap();
+        assert!(!latest_jackpot.drawing_paused, "{}", LotteryError::DrawingPaused);
+        assert!(latest_jackpot.storage_move.is_none(), "{}", LotteryError::JackpotStorageMoving);
+        let result = latest_jackpot.drawed_results.get(latest_jackpot.drawed_results.len() - 1).unwrap();
+
+        // Only the tickets indexed under the drawed numbers can win, check the next batch of them
+        let matched_ticket_ids = latest_jackpot.get_ticket_ids_by_mask(result.drawed_mask);
+        let no_of_tickets = matched_ticket_ids.len();
+        let from_index = latest_jackpot.settle_index.unwrap();
+        let to_index = std::cmp::min(from_index + limit.unwrap_or(DEFAULT_SETTLE_LIMIT), no_of_tickets);
+        let mut win_ticket_ids = Vec::new();
+        for i in from_index..to_index {
+            let ticket_id = matched_ticket_ids.get(i).unwrap();
             let ticket = self.tickets.get(&ticket_id).unwrap();
 
-            if compare_numbers(&ticket.picked_numbers, &result.drawed_numbers) {
+            if is_win_numbers(ticket.picked_mask, result.drawed_mask) {
                 // Add win ticket into list to track
-                latest_jackpot.win_ticket_ids.push(&ticket_id);
+                latest_jackpot.add_win_ticket(&ticket_id);
+                win_ticket_ids.push(ticket_id);
             }
         }
 
-        if !latest_jackpot.win_ticket_ids.is_empty() {
-            // Finalize current jackpot
-            latest_jackpot.end_time = Some(get_time_now());
-
-            // Devide the price for winner
-            let no_of_winners = latest_jackpot.win_ticket_ids.len();
-            let price_amount = latest_jackpot.locked_amount / no_of_winners as Balance;
-            for i in 0..no_of_winners {
-                let ticket_id = latest_jackpot.win_ticket_ids.get(i).unwrap();
-                let ticket = self.tickets.get(&ticket_id).unwrap();
-                let account_id = ticket.account_id;
+        if !win_ticket_ids.is_empty() {
+            log_win_tickets_found(latest_jackpot.id, &win_ticket_ids);
+        }
 
-                let mut account_info = self.get_account_info_or_default(&account_id);
+        let is_settled = to_index >= no_of_tickets;
+        if is_settled {
+            latest_jackpot.settle_index = None;
 
-                // Increase account balance & descrease locked amount
-                account_info.balance += price_amount;
-                latest_jackpot.locked_amount -= price_amount;
+            if !latest_jackpot.win_ticket_ids.is_empty() {
+                // Finalize current jackpot
+                latest_jackpot.end_time = Some(get_time_now());
 
-                self.account_infoes.insert(&account_id, &account_info);
+                // Devide the price for winner, the winners claim it later by themselves
+                latest_jackpot.prize_amount = split_prize(latest_jackpot.locked_amount, latest_jackpot.win_ticket_ids.len());
             }
+
+            log_jackpot_settled(&latest_jackpot);
+        }
+        else {
+            latest_jackpot.settle_index = Some(to_index);
         }
 
         self.update_latest_jackpot(&latest_jackpot);
 
-        if matches!(latest_jackpot.get_status(), JackpotStatus::Close) {
-            return true;
-        }
+        is_settled
+    }
+
+    pub fn claim_prize(&mut self, ticket_id: TicketId) {
+        let account_id = env::predecessor_account_id();
+
+        let ticket = self.tickets.get(&ticket_id);
+        assert!(ticket.is_some(), "{}", LotteryError::TicketNotFound);
+
+        let ticket = ticket.unwrap();
+        assert!(ticket.account_id == account_id, "{}", LotteryError::NotTicketOwner);
 
-        false
+        // Check the ticket has won and its prize is still claimable, the prize is only known once
+        // every batch is settled
+        let mut jackpot = self.jackpots.get((ticket.jackpot_id - 1).into()).unwrap();
+        assert!(!matches!(jackpot.get_status(), JackpotStatus::Settling), "{}", LotteryError::JackpotStillSettling);
+        assert!(jackpot.is_win_ticket(&ticket_id), "{}", LotteryError::TicketNotWon);
+        assert!(!jackpot.claimed_ticket_ids.contains(&ticket_id), "{}", LotteryError::PrizeAlreadyClaimed);
+        assert!(!jackpot.is_claim_expired(), "{}", LotteryError::ClaimExpired);
+
+        // Move the prize from the jackpot to the winner balance
+        let mut account_info = self.get_account_info_or_default(&account_id);
+        account_info.balance += jackpot.prize_amount;
+        jackpot.locked_amount -= jackpot.prize_amount;
+        self.fund_totals.player_balance += jackpot.prize_amount;
+        self.fund_totals.locked_amount -= jackpot.prize_amount;
+        jackpot.set_claimed(&ticket_id);
+
+        self.account_infoes.insert(&account_id, &account_info);
+        self.update_jackpot(&jackpot);
+
+        log_winner_paid(&ticket, jackpot.prize_amount);
     }
 }
 
@@ -386,16 +661,19 @@ mod tests {
     use super::*;
     use near_sdk::MockedBlockchain;
     use near_sdk::{testing_env, VMContext};
+    use near_sdk::test_utils::get_logs;
 
     const DEPOSIT_AMOUNT: u128 = 10 * ONE_NEAR;
+    // A realistic block time, the drawing needs it to generate distinct numbers
+    const BLOCK_TIMESTAMP: Timestamp = 1_640_995_200_123_456_789;
 
-    // mock the context for testing, notice "signer_account_id" that was accessed above from env::
+    // mock the context for testing, notice "predecessor_account_id" that was accessed above from env::
     fn get_context(input: Vec<u8>, is_view: bool) -> VMContext {
         VMContext {
             current_account_id: "alice_near".to_string(),
             signer_account_id: "bob_near".to_string(),
             signer_account_pk: vec![0, 1, 2],
-            predecessor_account_id: "carol_near".to_string(),
+            predecessor_account_id: "bob_near".to_string(),
             input,
             block_index: 0,
             block_timestamp: 11,
@@ -413,7 +691,7 @@ mod tests {
 
     #[test]
     fn get_default_owner_id() {
-        let context = get_context(vec![], true);
+        let context = get_context(vec![], false);
         testing_env!(context);
 
         let contract = Lottery::new(String::from("alice_near"));
@@ -430,6 +708,7 @@ mod tests {
         
         let mut contract = Lottery::new(String::from("carol_near"));
 
+        contract.storage_deposit(None, None);
         contract.deposit();
 
         assert_eq!(
@@ -450,6 +729,7 @@ mod tests {
         
         let mut contract = Lottery::new(String::from("carol_near"));
 
+        contract.storage_deposit(None, None);
         contract.deposit();
 
         assert_eq!(
@@ -480,19 +760,30 @@ mod tests {
 
         assert!(contract.get_latest_jackpot().is_none());
 
-        contract.create_jackpot(None, None);
+        contract.create_jackpot();
 
         assert!(contract.get_latest_jackpot().is_some());
-        assert_eq!(contract.get_jackpots().len(), 1);
+        assert_eq!(contract.get_jackpot_summaries(None, None).len(), 1);
 
-        contract.create_jackpot(None, None);
-        assert_eq!(contract.get_jackpots().len(), 1);
+        contract.create_jackpot();
+        assert_eq!(contract.get_jackpot_summaries(None, None).len(), 1);
     }
 
     #[test]
-    fn create_drawed_result() {
+    #[should_panic(expected = "ERR_NO_OPEN_JACKPOT: There is no open jackpot.")]
+    fn draw_without_jackpot() {
         let context = get_context(vec![], false);
         testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.draw_jackpot();
+    }
+
+    #[test]
+    fn create_drawed_result() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
         
         let result = DrawingResult::default();
         let numbers = result.drawed_numbers;
@@ -505,11 +796,12 @@ mod tests {
         let number = get_random_number(MAX_DRAWING_NUMBER.into(), 0);
         println!("Random number is: {}", number);
 
-        assert_eq!(numbers, numbers);
+        assert_eq!(Ok(numbers), validate_numbers(&numbers));
+        assert!((1..=MAX_DRAWING_NUMBER.into()).contains(&number));
     }
 
     #[test]
-    fn test_compare_numbers() {
+    fn test_count_matches() {
         let context = get_context(vec![], false);
         testing_env!(context);
 
@@ -518,9 +810,15 @@ mod tests {
         let number3 = [1, 2, 3, 4, 5, 7];
         let number4 = [2, 3, 4, 5, 6, 7];
 
-        assert_eq!(compare_numbers(&number1, &number2), true);
-        assert_eq!(compare_numbers(&number1, &number3), false);
-        assert_eq!(compare_numbers(&number3, &number4), false);
+        let mask1 = numbers_to_bitmask(&number1);
+        let mask2 = numbers_to_bitmask(&number2);
+        let mask3 = numbers_to_bitmask(&number3);
+        let mask4 = numbers_to_bitmask(&number4);
+
+        assert_eq!(count_matches(mask1, mask2), 6);
+        assert_eq!(count_matches(mask1, mask3), 5);
+        assert_eq!(count_matches(mask3, mask4), 5);
+        assert_eq!(count_matches(mask1, mask4), 5);
     }
 
     #[test]
@@ -534,12 +832,13 @@ mod tests {
 
         // Create a jackpot
         println!("Create a jackpot");
-        contract.create_jackpot(None, None);
+        contract.create_jackpot();
 
         assert!(contract.get_latest_jackpot().is_some());
 
         // Deposit fund to bob_near
         println!("Deposit fund to bob_near");
+        contract.storage_deposit(None, None);
         contract.deposit();
 
         assert_eq!(
@@ -570,7 +869,7 @@ mod tests {
         let deposit_amount_2_bought_ticket = ONE_NEAR * 18;
 
         let mut context = get_context(vec![], false);
-        context.signer_account_id = account_1.clone();
+        context.predecessor_account_id = account_1.clone();
         context.attached_deposit = deposit_amount_1;
         testing_env!(context);
 
@@ -580,12 +879,13 @@ mod tests {
 
         // Create a jackpot
         println!("Create a jackpot");
-        contract.create_jackpot(None, None);
+        contract.create_jackpot();
 
         assert!(contract.get_latest_jackpot().is_some());
 
         // Deposit fund to bob_near
         println!("Deposit fund to bob_near");
+        contract.storage_deposit(None, None);
         contract.deposit();
 
         // Buy ticket for bob_near
@@ -601,12 +901,13 @@ mod tests {
 
         // Get mock blockchain context for bob_near_2
         let mut context = get_context(vec![], false);
-        context.signer_account_id = account_2.clone();
+        context.predecessor_account_id = account_2.clone();
         context.attached_deposit = deposit_amount_2;
         testing_env!(context);
 
         // Deposit fund to bob_near_2
         println!("Deposit fund to bob_near_2");
+        contract.storage_deposit(None, None);
         contract.deposit();
 
         // Buy ticket for bob_near_2
@@ -640,26 +941,25 @@ mod tests {
         // Debug account infoes
         println!("---- Account Infoes ----");
         let vec = contract.account_infoes.to_vec();
-        for i in 0..vec.len() {
-            let (account_id, account_info) = &vec[i];
+        for (account_id, account_info) in &vec {
             println!("Account Id: {}, Balance: {}, Tickets: {:?}", account_id, account_info.balance, account_info.ticket_ids);
             let ticket_ids = account_info.ticket_ids.to_vec();
-            for j in 0..ticket_ids.len() {
-                println!("--> Ticket id {}: {}", j, ticket_ids[j]);
+            for (j, ticket_id) in ticket_ids.iter().enumerate() {
+                println!("--> Ticket id {}: {}", j, ticket_id);
             }
         }
         println!("---- End of Account Infoes ----");
 
         // ---------------- Test ticket ----------------
-        let tickets = contract.get_account_tickets(&account_1);
-        let account_1_ticket_1_actual = tickets.get(0).unwrap().picked_numbers;
+        let tickets = contract.get_account_tickets_paged(&account_1, None, None);
+        let account_1_ticket_1_actual = tickets.first().unwrap().picked_numbers;
         
         println!("Test ticket of account 1");
         assert_eq!(account_1_ticket_1_expected, account_1_ticket_1_actual);
         //assert_eq!(0, tickets.len());
 
-        let tickets = contract.get_account_tickets(&account_2);
-        let account_2_ticket_1_actual = tickets.get(0).unwrap().picked_numbers;
+        let tickets = contract.get_account_tickets_paged(&account_2, None, None);
+        let account_2_ticket_1_actual = tickets.first().unwrap().picked_numbers;
 
         println!("Test ticket of account 2");
         assert_eq!(account_2_ticket_1_expected, account_2_ticket_1_actual);
@@ -688,11 +988,1271 @@ mod tests {
 
     #[test]
     fn get_random_numbers() {
-        let context = get_context(vec![], false);
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
         testing_env!(context);
 
         let contract = Lottery::new(String::from("bob_near"));
 
-        assert_eq!(contract.get_number(), 0);
+        assert!((1..=MAX_DRAWING_NUMBER).contains(&contract.get_number()));
+    }
+
+    #[test]
+    fn claim_prize() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.config.game_type = GameType::GuaranteedWinner;
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+
+        // The only ticket wins, but the prize stays in the jackpot until it is claimed
+        contract.draw_jackpot();
+        contract.settle_jackpot(None);
+        let jackpot = contract.get_latest_jackpot().unwrap();
+        assert_eq!(1, jackpot.win_ticket_ids.len());
+        assert_eq!(DEPOSIT_AMOUNT + ONE_NEAR, jackpot.prize_amount);
+        assert_eq!(
+            U128::from(DEPOSIT_AMOUNT - ONE_NEAR),
+            contract.get_account_balance(&String::from("bob_near"))
+        );
+
+        // The claim deadline keeps running while paused, so claims are never paused
+        contract.set_paused(true);
+        contract.claim_prize(1);
+
+        let jackpot = contract.get_latest_jackpot().unwrap();
+        assert_eq!(0, jackpot.locked_amount);
+        assert!(jackpot.claimed_ticket_ids.contains(&1));
+        assert_eq!(
+            U128::from(2 * DEPOSIT_AMOUNT),
+            contract.get_account_balance(&String::from("bob_near"))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The prize has already been claimed.")]
+    fn claim_prize_twice() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.config.game_type = GameType::GuaranteedWinner;
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.draw_jackpot();
+        contract.settle_jackpot(None);
+
+        contract.claim_prize(1);
+        contract.claim_prize(1);
+    }
+
+    #[test]
+    fn rollover_expired_prize() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.config.game_type = GameType::GuaranteedWinner;
+        contract.config.claim_duration = 100;
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.draw_jackpot();
+        contract.settle_jackpot(None);
+
+        // Nobody claims the prize before the claim window has passed
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP + 1_000;
+        context.storage_usage = env::storage_usage();
+        context.attached_deposit = 0;
+        testing_env!(context);
+
+        contract.create_jackpot();
+
+        assert_eq!(0, contract.internal_get_jackpot(1).unwrap().locked_amount);
+        assert_eq!(DEPOSIT_AMOUNT + ONE_NEAR, contract.internal_get_jackpot(2).unwrap().locked_amount);
+    }
+
+    #[test]
+    fn settle_jackpot_in_batches() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.config.game_type = GameType::GuaranteedWinner;
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+
+        contract.draw_jackpot();
+        assert!(matches!(contract.get_latest_jackpot().unwrap().get_status(), JackpotStatus::Settling));
+
+        assert!(!contract.settle_jackpot(Some(2)));
+        let jackpot = contract.get_latest_jackpot().unwrap();
+        assert_eq!(Some(2), jackpot.settle_index);
+        assert!(jackpot.end_time.is_none());
+
+        assert!(contract.settle_jackpot(Some(2)));
+        let jackpot = contract.get_latest_jackpot().unwrap();
+        assert_eq!(None, jackpot.settle_index);
+        assert_eq!(3, jackpot.win_ticket_ids.len());
+        assert_eq!((DEPOSIT_AMOUNT + 3 * ONE_NEAR) / 3, jackpot.prize_amount);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_JACKPOT_STILL_SETTLING")]
+    fn claim_prize_while_settling() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.config.game_type = GameType::GuaranteedWinner;
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+
+        // The first winner is known after the first batch, but not the prize
+        contract.draw_jackpot();
+        assert!(!contract.settle_jackpot(Some(1)));
+        assert!(contract.get_latest_jackpot().unwrap().is_win_ticket(&1));
+        contract.claim_prize(1);
+    }
+
+    #[test]
+    fn draw_guaranteed_winner_jackpot() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.config.game_type = GameType::GuaranteedWinner;
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.buy_ticket([1, 2, 3, 4, 5, 7]);
+        contract.buy_ticket([1, 2, 3, 4, 5, 8]);
+
+        // The winning ticket is the one the drawing seed picks, anyone can check it
+        contract.draw_jackpot();
+        contract.settle_jackpot(None);
+        let jackpot = contract.get_latest_jackpot().unwrap();
+        let result = jackpot.drawed_results.get(0).unwrap();
+        let lucky_ticket_index = pick_lucky_ticket_index(result.seed, 3);
+        assert_eq!(Some(lucky_ticket_index), result.lucky_ticket_index);
+        assert_eq!(vec![jackpot.ticket_ids.get(lucky_ticket_index).unwrap()], jackpot.win_ticket_ids.to_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "There is no open jackpot.")]
+    fn buy_ticket_while_settling() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.draw_jackpot();
+
+        contract.buy_ticket([1, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn index_tickets_by_numbers() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([6, 5, 4, 3, 2, 1]);
+        contract.buy_ticket([1, 3, 4, 5, 6, 7]);
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+
+        let jackpot = contract.get_latest_jackpot().unwrap();
+        assert_eq!(vec![1, 3], jackpot.get_ticket_ids_by_mask(numbers_to_bitmask(&[1, 2, 3, 4, 5, 6])).to_vec());
+        assert_eq!(vec![2], jackpot.get_ticket_ids_by_mask(numbers_to_bitmask(&[1, 3, 4, 5, 6, 7])).to_vec());
+        assert!(jackpot.get_ticket_ids_by_mask(numbers_to_bitmask(&[1, 4, 5, 6, 7, 8])).is_empty());
+    }
+
+    #[test]
+    fn migrate_legacy_tickets() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+
+        // Store a ticket with the layout of the first release, the jackpot holds its id
+        let legacy_ticket = TicketV0 {
+            id: 2,
+            account_id: String::from("bob_near"),
+            picked_numbers: [2, 3, 4, 5, 6, 7],
+            created_time: 11,
+        };
+        contract.tickets.insert_raw(&2u64.try_to_vec().unwrap(), &legacy_ticket.try_to_vec().unwrap());
+        let mut jackpot = contract.internal_get_jackpot(1).unwrap();
+        jackpot.ticket_ids.push(&2);
+        contract.update_jackpot(&jackpot);
+
+        assert_eq!(1, contract.migrate_tickets(0, 10));
+        assert_eq!(0, contract.migrate_tickets(0, 10));
+
+        let ticket = contract.tickets.get(&2).unwrap();
+        assert_eq!([2, 3, 4, 5, 6, 7], ticket.picked_numbers);
+        assert_eq!(numbers_to_bitmask(&[2, 3, 4, 5, 6, 7]), ticket.picked_mask);
+        assert_eq!(1, ticket.jackpot_id);
+    }
+
+    // Borsh snapshots of state version 1, taken after creating a jackpot and buying a ticket at
+    // block time 11. They must keep deserializing as the latest layout until a version is added.
+    const LOTTERY_SNAPSHOT: &str = "08000000626f625f6e656172020000000069010000000000000002000000006b010000000000000002000000007601000000000000000100000001020000000269010000000000000002000000026b0100000000000000020000000276000000000000000000009e22299d0000007b01000000000000020000000969000000000000000002000000096b000000000000000002000000097600000000020000000a690000000000000000020000000a6b0000000000000000020000000a760000000000000000000000a1edccce1bc2d300000000000000004207693509000000000000000100000000000000010000000b00000000000000000000000000000000000000a95a3445fad2710700000000000000004a480114169545080000000000000000eb35cee23157190900000000000000000000000000000000000000000000";
+    const LOTTERY_V0_SNAPSHOT: &str = "08000000626f625f6e6561720d0000006163636f756e745f696e666f6901000000000000000d0000006163636f756e745f696e666f6b01000000000000000d0000006163636f756e745f696e666f760100000000000000070000006a61636b706f74070000007469636b6574690100000000000000070000007469636b65746b0100000000000000070000007469636b657476";
+    const JACKPOT_SNAPSHOT: &str = "01000000000000a1edccce1bc2d3000000000000000000eb35cee23157190900000000000100000000000000050000000401000000000000000000000005000000050100000000000000000000000500000006010000000b00000000000000000b00000000000000000042076935090000000000000000000000000000000000050000000701000000050000000d010000000005000000080100000000000000000000000000";
+    const TICKET_SNAPSHOT: &str = "010000000000000008000000626f625f6e6561720102030405060b00000000000000010000007e00000000000000";
+    const ACCOUNT_INFO_SNAPSHOT: &str = "000000a95a3445fad271070000000000010000000000000021000000032c366d5b890b0b27f261991541c1fe62a9e0fd1039a283a9d85126c6ffb6ccb20b000000000000000000004a4801141695450800000000007803000000000000";
+    const ACCOUNT_INFO_V0_SNAPSHOT: &str = "000000a95a3445fad271070000000000010000000000000021000000032c366d5b890b0b27f261991541c1fe62a9e0fd1039a283a9d85126c6ffb6ccb20b00000000000000";
+    // The same state written before the `StorageKey` prefixes
+    const LEGACY_PREFIX_ACCOUNT_INFO_SNAPSHOT: &str = "000000a95a3445fad27107000000000001000000000000000a0000007461626f625f6e6561720b00000000000000";
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn deserialize_state_snapshot() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+
+        // The current code still writes the snapshot layout
+        assert_eq!(from_hex(LOTTERY_SNAPSHOT), contract.try_to_vec().unwrap());
+        assert_eq!(from_hex(JACKPOT_SNAPSHOT), contract.jackpots.get_raw(0).unwrap());
+        assert_eq!(from_hex(TICKET_SNAPSHOT), contract.tickets.values_as_vector().get_raw(0).unwrap());
+        assert_eq!(from_hex(ACCOUNT_INFO_SNAPSHOT), contract.account_infoes.values_as_vector().get_raw(0).unwrap());
+
+        // The state is read with the layout of its recorded version
+        let state = VersionedLottery::read(&from_hex(LOTTERY_SNAPSHOT));
+        assert_eq!(STATE_VERSION, state.version());
+        assert_eq!("bob_near", Lottery::from(state).owner_id);
+
+        let state = VersionedLottery::from_versioned_slice(0, &from_hex(LOTTERY_V0_SNAPSHOT));
+        assert_eq!(0, state.version());
+        assert_eq!(DEFAULT_UPGRADE_TIMELOCK, Lottery::from(state).upgrade_timelock);
+
+        assert!(matches!(VersionedJackpot::from_slice(&from_hex(JACKPOT_SNAPSHOT)), VersionedJackpot::V1(_)));
+        assert!(matches!(VersionedTicket::from_slice(&from_hex(TICKET_SNAPSHOT)), VersionedTicket::V1(_)));
+
+        let account_info = AccountInfo::try_from_slice(&from_hex(ACCOUNT_INFO_SNAPSHOT)).unwrap();
+        assert_eq!(DEPOSIT_AMOUNT - ONE_NEAR, account_info.balance);
+        assert!(matches!(VersionedAccountInfo::from_slice(&from_hex(ACCOUNT_INFO_V0_SNAPSHOT)), VersionedAccountInfo::V0(_)));
+
+        // Legacy prefixes are only a different key, not a different layout
+        assert!(matches!(VersionedAccountInfo::from_slice(&from_hex(LEGACY_PREFIX_ACCOUNT_INFO_SNAPSHOT)), VersionedAccountInfo::V0(_)));
+    }
+
+    #[test]
+    fn migrate_first_release_state() {
+        let mut context = get_context(vec![], false);
+        context.predecessor_account_id = context.current_account_id.clone();
+        testing_env!(context.clone());
+
+        // Store the state the way the first release did, with a closed jackpot and an open one
+        let mut state = LotteryV0 {
+            owner_id: String::from("bob_near"),
+            account_infoes: UnorderedMap::new(b"account_info".to_vec()),
+            jackpots: Vector::new(b"jackpot".to_vec()),
+            tickets: UnorderedMap::new(b"ticket".to_vec()),
+        };
+
+        let tickets: [(TicketId, JackpotId, [u8; 6]); 3] = [(1, 1, [1, 2, 3, 4, 5, 6]), (2, 2, [2, 3, 4, 5, 6, 7]), (3, 2, [1, 2, 3, 4, 5, 6])];
+        for (ticket_id, _, picked_numbers) in tickets.iter() {
+            let ticket = TicketV0 { id: *ticket_id, account_id: String::from("bob_near"), picked_numbers: *picked_numbers, created_time: 11 };
+            state.tickets.insert_raw(&ticket_id.try_to_vec().unwrap(), &ticket.try_to_vec().unwrap());
+        }
+
+        for jackpot_id in 1..3 {
+            let mut jackpot = JackpotV0 {
+                id: jackpot_id,
+                ticket_price: ONE_NEAR,
+                locked_amount: 0,
+                ticket_ids: Vector::new(format!("tj{}", jackpot_id).as_bytes()),
+                win_ticket_ids: Vector::new(format!("tjw{}", jackpot_id).as_bytes()),
+                drawed_results: Vector::new(format!("dr{}", jackpot_id).as_bytes()),
+                start_time: 11,
+                end_time: None,
+                created_time: 11,
+            };
+            for (ticket_id, _, _) in tickets.iter().filter(|(_, id, _)| *id == jackpot_id) {
+                jackpot.ticket_ids.push(ticket_id);
+            }
+
+            // The first jackpot has been won and paid
+            if jackpot_id == 1 {
+                jackpot.win_ticket_ids.push(&1);
+                jackpot.end_time = Some(11);
+                jackpot.drawed_results.push_raw(&DrawingResultV0 { drawed_numbers: [1, 2, 3, 4, 5, 6], created_time: 11 }.try_to_vec().unwrap());
+                jackpot.drawed_results.push_raw(&DrawingResultV0 { drawed_numbers: [2, 3, 4, 5, 6, 7], created_time: 12 }.try_to_vec().unwrap());
+            }
+            state.jackpots.push_raw(&jackpot.try_to_vec().unwrap());
+        }
+
+        let mut account_info = AccountInfoV0 { balance: ONE_NEAR, ticket_ids: Vector::new(b"tabob_near".to_vec()), created_time: 11 };
+        for (ticket_id, _, _) in tickets.iter() {
+            account_info.ticket_ids.push(ticket_id);
+        }
+        state.account_infoes.insert_raw(&String::from("bob_near").try_to_vec().unwrap(), &account_info.try_to_vec().unwrap());
+        env::state_write(&state);
+
+        assert!(!env::storage_has_key(b"STATE_VERSION"));
+        let mut contract = Lottery::migrate();
+        assert_eq!(Some(STATE_VERSION.try_to_vec().unwrap()), env::storage_read(b"STATE_VERSION"));
+        assert_eq!(ONE_NEAR, contract.get_balance_sheet().player_balance);
+
+        // The collections are migrated by the owner
+        context.predecessor_account_id = String::from("bob_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        assert_eq!(3, contract.migrate_tickets(0, 10));
+        assert_eq!(2, contract.migrate_jackpots(0, 10));
+        assert_eq!(2, contract.migrate_drawed_results(1));
+        assert_eq!(0, contract.migrate_jackpots(0, 10));
+
+        assert_eq!(2, contract.get_ticket(2).unwrap().jackpot_id);
+        assert_eq!(numbers_to_bitmask(&[1, 2, 3, 4, 5, 6]), contract.get_jackpot_drawed_results(1, None, None)[0].drawed_mask);
+        assert_eq!(0, contract.get_jackpot_drawed_results(1, None, None)[1].version);
+        assert_eq!(None, contract.get_jackpot_drawed_results(1, None, None)[1].lucky_ticket_index);
+
+        let won_jackpot = contract.internal_get_jackpot(1).unwrap();
+        assert!(won_jackpot.claimed_ticket_ids.contains(&1));
+
+        // The tickets of the open jackpot are indexed in batches, it can't be drawn until then
+        assert_eq!(Some(0), contract.internal_get_jackpot(2).unwrap().index_cursor);
+        assert!(!contract.migrate_ticket_index(2, 1));
+        assert!(contract.migrate_ticket_index(2, 1));
+        assert!(contract.migrate_ticket_index(2, 1));
+
+        // The open jackpot can be settled against its indexed tickets
+        let open_jackpot = contract.internal_get_jackpot(2).unwrap();
+        assert!(open_jackpot.index_cursor.is_none());
+        assert!(matches!(open_jackpot.get_status(), JackpotStatus::Open));
+        assert_eq!(vec![3], open_jackpot.get_ticket_ids_by_mask(numbers_to_bitmask(&[1, 2, 3, 4, 5, 6])).to_vec());
+
+        assert_eq!(1, contract.migrate_accounts(0, 10));
+        assert_eq!(0, contract.migrate_accounts(0, 10));
+        assert_eq!(U128::from(ONE_NEAR), contract.get_account_balance(&String::from("bob_near")));
+
+        // Then the collections are moved under the `StorageKey` prefixes
+        assert_eq!(1, contract.migrate_account_storage(0, 10));
+        assert_eq!(0, contract.migrate_account_storage(0, 10));
+
+        // A jackpot is moved in batches, it keeps its collections until all of them are copied
+        assert!(!contract.migrate_jackpot_storage(1, 2));
+        assert!(contract.internal_get_jackpot(1).unwrap().is_win_ticket(&1));
+        let mut no_of_batches = 1;
+        while !contract.migrate_jackpot_storage(1, 2) {
+            assert!(contract.internal_get_jackpot(1).unwrap().claimed_ticket_ids.contains(&1));
+            no_of_batches += 1;
+        }
+        assert!(no_of_batches > 2);
+        assert!(contract.migrate_jackpot_storage(2, 100));
+        assert!(contract.migrate_jackpot_storage(2, 100));
+        assert!(!env::storage_has_key(&[b"tj1".to_vec(), 0u64.to_le_bytes().to_vec()].concat()));
+        assert!(!env::storage_has_key(&[b"dr1".to_vec(), 1u64.to_le_bytes().to_vec()].concat()));
+        assert!(!env::storage_has_key(&[b"tabob_near".to_vec(), 0u64.to_le_bytes().to_vec()].concat()));
+        assert!(!env::storage_has_key(&[b"tj2".to_vec(), 0u64.to_le_bytes().to_vec()].concat()));
+
+        assert_eq!(3, contract.get_account_tickets_paged(&String::from("bob_near"), None, None).len());
+        assert!(contract.internal_get_jackpot(1).unwrap().claimed_ticket_ids.contains(&1));
+        assert_eq!(2, contract.get_jackpot_drawed_results(1, None, None).len());
+        assert_eq!(vec![3], contract.internal_get_jackpot(2).unwrap().get_ticket_ids_by_mask(numbers_to_bitmask(&[1, 2, 3, 4, 5, 6])).to_vec());
+    }
+
+    #[test]
+    fn move_jackpot_storage_in_batches() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.config.game_type = GameType::GuaranteedWinner;
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.draw_jackpot();
+        contract.settle_jackpot(None);
+        contract.claim_prize(1);
+
+        // Put the collections back under the ASCII prefixes used before `StorageKey`
+        let mut jackpot = contract.internal_get_jackpot(1).unwrap();
+        let mut ticket_ids = Vector::new(b"tj1".to_vec());
+        ticket_ids.extend(jackpot.ticket_ids.iter());
+        let mut win_ticket_ids = Vector::new(b"tjw1".to_vec());
+        win_ticket_ids.extend(jackpot.win_ticket_ids.iter());
+        let mut drawed_results = Vector::new(b"dr1".to_vec());
+        drawed_results.extend_raw(jackpot.drawed_results.iter_raw());
+        jackpot.ticket_ids.clear();
+        jackpot.win_ticket_ids.clear();
+        jackpot.drawed_results.clear();
+        jackpot.ticket_ids = ticket_ids;
+        jackpot.win_ticket_ids = win_ticket_ids;
+        jackpot.drawed_results = drawed_results;
+        contract.update_jackpot(&jackpot);
+
+        // A prize claimed in the middle of the move is kept, and nothing is left under the legacy prefixes
+        assert!(!contract.migrate_jackpot_storage(1, 1));
+        contract.claim_prize(2);
+        while !contract.migrate_jackpot_storage(1, 1) {}
+
+        let jackpot = contract.internal_get_jackpot(1).unwrap();
+        assert!(jackpot.storage_move.is_none());
+        assert_eq!(vec![1, 2], jackpot.win_ticket_ids.to_vec());
+        assert!(jackpot.claimed_ticket_ids.contains(&1) && jackpot.claimed_ticket_ids.contains(&2));
+        assert_eq!(1, contract.get_jackpot_drawed_results(1, None, None).len());
+        assert!(!env::storage_has_key(&[b"tj1".to_vec(), 0u64.to_le_bytes().to_vec()].concat()));
+        assert!(!env::storage_has_key(&[b"tjw1".to_vec(), 1u64.to_le_bytes().to_vec()].concat()));
+    }
+
+    #[test]
+    fn storage_management() {
+        let mut context = get_context(vec![], false);
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.create_jackpot();
+        assert!(contract.storage_balance_of(String::from("bob_near")).is_none());
+
+        // Only the registration cost is kept, the rest is refunded
+        let min_amount = contract.storage_balance_bounds().min.0;
+        let storage_balance = contract.storage_deposit(None, Some(true));
+        assert_eq!(min_amount, storage_balance.total.0);
+        assert_eq!(0, storage_balance.available.0);
+
+        // Tickets are paid from the storage balance
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        let storage_balance = contract.storage_balance_of(String::from("bob_near")).unwrap();
+        assert!(storage_balance.available.0 < DEPOSIT_AMOUNT);
+
+        context.storage_usage = env::storage_usage();
+        context.account_balance = 100 * ONE_NEAR;
+        context.attached_deposit = 1;
+        testing_env!(context);
+
+        let storage_balance = contract.storage_withdraw(None);
+        assert_eq!(0, storage_balance.available.0);
+        assert!(storage_balance.total.0 > min_amount);
+    }
+
+    #[test]
+    fn measure_storage_keeps_accounts() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        let account_storage_usage = contract.account_storage_usage;
+
+        // An implicit account id is as long as the measured one
+        let account_id = "a".repeat(64);
+        contract.storage_deposit(Some(account_id.clone()), Some(true));
+        contract.measure_account_storage_usage();
+        assert!(contract.storage_balance_of(account_id).is_some());
+        assert_eq!(account_storage_usage, contract.account_storage_usage);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_STORAGE_BALANCE")]
+    fn buy_ticket_without_storage_balance() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.create_jackpot();
+        contract.storage_deposit(None, Some(true));
+        contract.deposit();
+
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn storage_unregister() {
+        let mut context = get_context(vec![], false);
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("carol_near"));
+        contract.storage_deposit(None, None);
+        contract.deposit();
+
+        context.storage_usage = env::storage_usage();
+        context.account_balance = 100 * ONE_NEAR;
+        context.attached_deposit = 1;
+        testing_env!(context);
+
+        // The balance is refunded with the storage deposit
+        assert!(contract.storage_unregister(Some(true)));
+        assert!(contract.storage_balance_of(String::from("bob_near")).is_none());
+        assert!(!contract.storage_unregister(None));
+    }
+
+    #[test]
+    fn grant_and_revoke_roles() {
+        let mut context = get_context(vec![], false);
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        let operator_id = String::from("dave_near");
+        assert_eq!(Role::ALL.to_vec(), contract.get_roles(String::from("bob_near")));
+        assert!(!contract.has_role(Role::Operator, operator_id.clone()));
+
+        contract.grant_role(Role::Operator, operator_id.clone());
+        assert!(contract.has_role(Role::Operator, operator_id.clone()));
+        assert!(!contract.has_role(Role::Drawer, operator_id.clone()));
+        assert_eq!(vec![operator_id.clone()], contract.get_role_members(Role::Operator, None, None));
+        assert!(contract.get_role_members(Role::Drawer, None, None).is_empty());
+
+        // The operator creates jackpots without being the owner
+        context.predecessor_account_id = operator_id.clone();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.create_jackpot();
+
+        context.predecessor_account_id = String::from("bob_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.revoke_role(Role::Operator, operator_id.clone());
+        assert!(contract.get_roles(operator_id).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MISSING_ROLE")]
+    fn draw_without_drawer_role() {
+        let mut context = get_context(vec![], false);
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.grant_role(Role::Operator, String::from("dave_near"));
+
+        context.predecessor_account_id = String::from("dave_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.create_jackpot();
+
+        contract.draw_jackpot();
+    }
+
+    #[test]
+    fn transfer_ownership() {
+        let mut context = get_context(vec![], false);
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.propose_owner(String::from("dave_near"));
+        assert_eq!(Some(String::from("dave_near")), contract.get_proposed_owner_id());
+        assert_eq!("bob_near", contract.get_owner_id());
+
+        context.predecessor_account_id = String::from("dave_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+
+        contract.accept_ownership();
+        assert_eq!("dave_near", contract.get_owner_id());
+        assert!(contract.get_proposed_owner_id().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_PROPOSED_OWNER")]
+    fn accept_cancelled_ownership() {
+        let mut context = get_context(vec![], false);
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.propose_owner(String::from("dave_near"));
+        contract.cancel_ownership_proposal();
+
+        context.predecessor_account_id = String::from("dave_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+
+        contract.accept_ownership();
+    }
+
+    #[test]
+    fn cross_contract_player() {
+        let mut context = get_context(vec![], false);
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.create_jackpot();
+
+        // A syndicate contract plays for itself, whoever signed the transaction
+        context.predecessor_account_id = String::from("syndicate_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+
+        let tickets = contract.get_account_tickets_paged(&String::from("syndicate_near"), None, None);
+        assert_eq!(1, tickets.len());
+        assert_eq!("syndicate_near", tickets[0].account_id);
+        assert_eq!(U128::from(DEPOSIT_AMOUNT - ONE_NEAR), contract.get_account_balance(&String::from("syndicate_near")));
+        assert!(contract.get_account_tickets_paged(&String::from("bob_near"), None, None).is_empty());
+        assert!(contract.storage_balance_of(String::from("bob_near")).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_OWNER")]
+    fn owner_signed_call_through_other_contract() {
+        let mut context = get_context(vec![], false);
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+
+        // The owner signed a call to another contract, which calls the lottery in turn
+        context.predecessor_account_id = String::from("evil_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+
+        contract.propose_owner(String::from("evil_near"));
+    }
+
+    #[test]
+    fn pause_and_withdraw() {
+        let mut context = get_context(vec![], false);
+        context.account_balance = 100 * ONE_NEAR;
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.grant_role(Role::Guardian, String::from("dave_near"));
+
+        // A guardian pauses, only the owner resumes
+        context.predecessor_account_id = String::from("dave_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.set_paused(true);
+        contract.set_jackpot_sales_paused(1, true);
+        assert!(contract.is_paused());
+        assert!(contract.internal_get_jackpot(1).unwrap().sales_paused);
+
+        // Players can still leave
+        context.predecessor_account_id = String::from("bob_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.withdraw();
+        assert_eq!(U128::from(0), contract.get_account_balance(&String::from("bob_near")));
+
+        contract.set_paused(false);
+        assert!(!contract.is_paused());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CONTRACT_PAUSED")]
+    fn buy_ticket_while_paused() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.set_paused(true);
+
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DRAWING_PAUSED")]
+    fn draw_paused_jackpot() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.create_jackpot();
+        contract.set_jackpot_drawing_paused(1, true);
+
+        contract.draw_jackpot();
+    }
+
+    #[test]
+    fn wind_down_and_refund_tickets() {
+        let mut context = get_context(vec![], false);
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.config.game_type = GameType::GuaranteedWinner;
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+
+        context.predecessor_account_id = String::from("carol_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+
+        // The jackpot is closed while settling, the winner found so far can't claim
+        context.predecessor_account_id = String::from("bob_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.draw_jackpot();
+        assert!(!contract.settle_jackpot(Some(1)));
+        contract.start_wind_down();
+        let jackpot = contract.internal_get_jackpot(1).unwrap();
+        assert!(jackpot.settle_index.is_none() && jackpot.end_time.is_some());
+        assert!(jackpot.is_refunded && jackpot.win_ticket_ids.len() == 1);
+        assert!(!jackpot.is_win_ticket(&jackpot.win_ticket_ids.get(0).unwrap()));
+        assert_eq!(DEPOSIT_AMOUNT + 3 * ONE_NEAR, jackpot.locked_amount);
+
+        // Anyone can push the refunds forward
+        context.predecessor_account_id = String::from("carol_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        assert!(!contract.refund_tickets(Some(2)));
+        assert!(contract.refund_tickets(Some(2)));
+        assert!(contract.refund_tickets(None));
+
+        assert_eq!(U128::from(DEPOSIT_AMOUNT), contract.get_account_balance(&String::from("bob_near")));
+        assert_eq!(U128::from(DEPOSIT_AMOUNT), contract.get_account_balance(&String::from("carol_near")));
+        assert!(contract.get_wind_down().unwrap().refund_jackpot_id.is_none());
+
+        // The seed is released to the treasury and the books still balance
+        assert_eq!(0, contract.internal_get_jackpot(1).unwrap().locked_amount);
+        assert_eq!(DEPOSIT_AMOUNT, contract.get_treasury().0);
+        let balance_sheet = contract.get_balance_sheet();
+        assert_eq!(0, balance_sheet.locked_amount);
+        assert_eq!(5 * DEPOSIT_AMOUNT, balance_sheet.get_total());
+    }
+
+    #[test]
+    fn wind_down_and_refund_ticket_fees() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.config.fee_bps = 500;
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.buy_ticket([1, 2, 3, 4, 5, 7]);
+
+        // The fees of the open jackpot stay in the treasury to be refunded
+        let fee = ONE_NEAR / 20;
+        assert_eq!(2 * fee, contract.get_treasury().0);
+        assert_eq!(2 * fee, contract.get_refundable_fees());
+
+        contract.start_wind_down();
+        assert!(!contract.refund_tickets(Some(1)));
+        assert_eq!(fee, contract.get_refundable_fees());
+        assert!(contract.refund_tickets(None));
+
+        // Every ticket is refunded its whole price
+        assert_eq!(U128::from(DEPOSIT_AMOUNT), contract.get_account_balance(&String::from("bob_near")));
+        assert_eq!(DEPOSIT_AMOUNT, contract.get_treasury().0);
+        assert_eq!(0, contract.get_refundable_fees());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_WINDING_DOWN")]
+    fn deposit_after_wind_down() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.storage_deposit(None, None);
+        contract.start_wind_down();
+        assert!(contract.refund_tickets(None));
+
+        contract.deposit();
+    }
+
+    #[test]
+    fn multisig_proposals() {
+        let mut context = get_context(vec![], false);
+        context.account_balance = 100 * ONE_NEAR;
+        context.attached_deposit = 0;
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.set_multisig(vec![String::from("carol_near"), String::from("dave_near")], 2, None);
+
+        context.predecessor_account_id = String::from("carol_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        let proposal_id = contract.propose(ProposalAction::GrantRole { role: Role::Drawer, account_id: String::from("erin_near") });
+        assert_eq!(1, contract.get_proposals(None, None).len());
+        assert!(!contract.has_role(Role::Drawer, String::from("erin_near")));
+
+        // The jackpot is seeded with the deposit attached to the proposal
+        context.attached_deposit = DEPOSIT_AMOUNT;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        let jackpot_proposal_id = contract.propose(ProposalAction::CreateJackpot);
+
+        context.predecessor_account_id = String::from("dave_near");
+        context.attached_deposit = 0;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        assert!(contract.approve(proposal_id));
+        assert!(contract.approve(jackpot_proposal_id));
+        assert!(contract.has_role(Role::Drawer, String::from("erin_near")));
+        assert_eq!(DEPOSIT_AMOUNT, contract.internal_get_jackpot(1).unwrap().locked_amount);
+
+        // A proposal which is not approved is removed by its proposer
+        let proposal_id = contract.propose(ProposalAction::ProposeOwner { owner_id: String::from("dave_near") });
+        contract.remove_proposal(proposal_id);
+        assert!(contract.get_proposals(None, None).is_empty());
+        assert!(contract.get_proposed_owner_id().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MULTISIG_REQUIRED")]
+    fn grant_role_with_multisig() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.set_multisig(vec![String::from("bob_near"), String::from("carol_near")], 2, None);
+
+        contract.grant_role(Role::Drawer, String::from("erin_near"));
+    }
+
+    #[test]
+    fn multisig_config_and_wind_down() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        context.attached_deposit = 0;
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.set_multisig(vec![String::from("carol_near"), String::from("dave_near")], 2, None);
+
+        context.predecessor_account_id = String::from("carol_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        let config_proposal_id = contract.propose(ProposalAction::ScheduleConfig {
+            ticket_price: U128::from(2 * ONE_NEAR),
+            claim_duration: U64::from(MIN_CLAIM_DURATION),
+            game_type: GameType::Standard,
+            fee_bps: 500,
+            effective_time: U64::from(BLOCK_TIMESTAMP + MIN_CONFIG_DELAY),
+        });
+        let wind_down_proposal_id = contract.propose(ProposalAction::StartWindDown);
+        assert!(contract.get_queued_config().is_none());
+        assert!(contract.get_wind_down().is_none());
+
+        context.predecessor_account_id = String::from("dave_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        assert!(contract.approve(config_proposal_id));
+        assert!(contract.approve(wind_down_proposal_id));
+        assert_eq!(500, contract.get_queued_config().unwrap().config.fee_bps);
+        assert!(contract.get_wind_down().is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MULTISIG_REQUIRED")]
+    fn schedule_config_with_multisig() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.set_multisig(vec![String::from("bob_near"), String::from("carol_near")], 2, None);
+
+        contract.schedule_config(U128::from(2 * ONE_NEAR), U64::from(MIN_CLAIM_DURATION), GameType::Standard, 0, U64::from(BLOCK_TIMESTAMP + MIN_CONFIG_DELAY));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MULTISIG_REQUIRED")]
+    fn start_wind_down_with_multisig() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.set_multisig(vec![String::from("bob_near"), String::from("carol_near")], 2, None);
+
+        contract.start_wind_down();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_EXPIRED")]
+    fn approve_expired_proposal() {
+        let mut context = get_context(vec![], false);
+        context.attached_deposit = 0;
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.set_multisig(vec![String::from("bob_near"), String::from("carol_near")], 2, Some(U64::from(100)));
+        let proposal_id = contract.propose(ProposalAction::RevokeRole { role: Role::Drawer, account_id: String::from("erin_near") });
+
+        context.predecessor_account_id = String::from("carol_near");
+        context.block_timestamp += 101;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.approve(proposal_id);
+    }
+
+    #[test]
+    fn schedule_config() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.config.game_type = GameType::GuaranteedWinner;
+        contract.create_jackpot();
+        contract.schedule_config(U128::from(2 * ONE_NEAR), U64::from(MIN_CLAIM_DURATION), GameType::Standard, 500, U64::from(BLOCK_TIMESTAMP + MIN_CONFIG_DELAY));
+        assert_eq!(ONE_NEAR, contract.get_config().ticket_price);
+
+        // The open jackpot keeps the price it was created with
+        context.block_timestamp = BLOCK_TIMESTAMP + MIN_CONFIG_DELAY;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        assert_eq!(2 * ONE_NEAR, contract.get_config().ticket_price);
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        assert_eq!(DEPOSIT_AMOUNT - ONE_NEAR, contract.get_account_balance(&String::from("bob_near")).0);
+        contract.draw_jackpot();
+        contract.settle_jackpot(None);
+
+        context.block_timestamp += 1;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.create_jackpot();
+        let jackpot = contract.internal_get_jackpot(2).unwrap();
+        assert_eq!(2 * ONE_NEAR, jackpot.ticket_price);
+        assert_eq!(MIN_CLAIM_DURATION, jackpot.claim_duration);
+        assert!(jackpot.game_type == GameType::Standard);
+        assert_eq!(500, jackpot.fee_bps);
+        assert!(contract.get_queued_config().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CONFIG_TOO_EARLY")]
+    fn schedule_config_too_early() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.schedule_config(U128::from(2 * ONE_NEAR), U64::from(MIN_CLAIM_DURATION), GameType::Standard, 0, U64::from(BLOCK_TIMESTAMP + MIN_CONFIG_DELAY - 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CLAIM_DURATION_TOO_SHORT")]
+    fn schedule_config_short_claim_duration() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.schedule_config(U128::from(2 * ONE_NEAR), U64::from(MIN_CLAIM_DURATION - 1), GameType::Standard, 0, U64::from(BLOCK_TIMESTAMP + MIN_CONFIG_DELAY));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_FEE")]
+    fn schedule_excessive_fee() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.schedule_config(U128::from(2 * ONE_NEAR), U64::from(MIN_CLAIM_DURATION), GameType::Standard, MAX_FEE_BPS + 1, U64::from(BLOCK_TIMESTAMP + MIN_CONFIG_DELAY));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_TICKET_PRICE")]
+    fn schedule_free_tickets() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.schedule_config(U128::from(0), U64::from(MIN_CLAIM_DURATION), GameType::Standard, 0, U64::from(BLOCK_TIMESTAMP + MIN_CONFIG_DELAY));
+    }
+
+    #[test]
+    fn take_ticket_fee_into_treasury() {
+        let mut context = get_context(vec![], false);
+        context.account_balance = 100 * ONE_NEAR;
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.config.fee_bps = 500;
+        contract.config.game_type = GameType::GuaranteedWinner;
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+
+        // 5% of the ticket price goes to the treasury, the rest to the pot
+        let fee = ONE_NEAR / 20;
+        assert_eq!(fee, contract.get_treasury().0);
+        assert_eq!(DEPOSIT_AMOUNT + ONE_NEAR - fee, contract.get_latest_jackpot().unwrap().locked_amount);
+
+        let balance_sheet = contract.get_balance_sheet();
+        assert_eq!(DEPOSIT_AMOUNT - ONE_NEAR, balance_sheet.player_balance);
+        assert_eq!(3 * DEPOSIT_AMOUNT, balance_sheet.get_total());
+
+        // The fees can be withdrawn once the jackpot is closed
+        contract.draw_jackpot();
+        contract.settle_jackpot(None);
+        context.block_timestamp += 1;
+        context.storage_usage = env::storage_usage();
+        context.attached_deposit = 0;
+        testing_env!(context);
+        contract.withdraw_treasury(None);
+        assert_eq!(0, contract.get_treasury().0);
+        assert_eq!(3 * DEPOSIT_AMOUNT - fee, contract.get_balance_sheet().get_total());
+        let entry = serde_json::to_value(&contract.get_audit_log(Some(contract.get_audit_log_length() - 1), None)[0]).unwrap();
+        assert_eq!("treasury_withdrawn", entry["action"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_TREASURY")]
+    fn withdraw_treasury_over_balance() {
+        let mut context = get_context(vec![], false);
+        context.attached_deposit = 0;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.withdraw_treasury(Some(U128::from(1)));
+    }
+
+    #[test]
+    fn audit_privileged_actions() {
+        let mut context = get_context(vec![], false);
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.create_jackpot();
+        contract.grant_role(Role::Guardian, String::from("dave_near"));
+
+        context.predecessor_account_id = String::from("dave_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.set_paused(true);
+
+        // Players' actions are not audited
+        assert_eq!(3, contract.get_audit_log_length());
+        let entries = contract.get_audit_log(Some(1), None);
+        assert_eq!(2, entries.len());
+        assert_eq!("dave_near", entries[1].account_id);
+
+        let entry = serde_json::to_value(&contract.get_audit_log(None, Some(1))[0]).unwrap();
+        assert_eq!("jackpot_created", entry["action"]);
+        assert_eq!(DEPOSIT_AMOUNT.to_string(), entry["params"]["initialized_amount"]);
+    }
+
+    #[test]
+    fn stage_and_deploy_upgrade() {
+        let mut context = get_context(vec![], false);
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        let code = vec![0, 97, 115, 109];
+        let code_hash = env::sha256(&code);
+        contract.stage_upgrade(Base58CryptoHash::from(CryptoHash::try_from(code_hash.as_slice()).unwrap()));
+
+        let staged_upgrade = contract.get_staged_upgrade().unwrap();
+        assert_eq!(11 + DEFAULT_UPGRADE_TIMELOCK, staged_upgrade.deployable_time);
+
+        // Deployable once the timelock is over
+        context.block_timestamp = 11 + DEFAULT_UPGRADE_TIMELOCK;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+
+        contract.deploy_upgrade(Base64VecU8::from(code));
+        assert!(contract.get_staged_upgrade().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_UPGRADE_TIMELOCKED")]
+    fn deploy_upgrade_before_timelock() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        let code = vec![0, 97, 115, 109];
+        let code_hash = env::sha256(&code);
+        contract.stage_upgrade(Base58CryptoHash::from(CryptoHash::try_from(code_hash.as_slice()).unwrap()));
+
+        contract.deploy_upgrade(Base64VecU8::from(code));
+    }
+
+    #[test]
+    fn change_upgrade_timelock() {
+        let mut context = get_context(vec![], false);
+        context.attached_deposit = 0;
+        testing_env!(context.clone());
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        let code_hash = Base58CryptoHash::from(CryptoHash::try_from(env::sha256(&[0, 97, 115, 109]).as_slice()).unwrap());
+
+        // A shorter timelock waits for the current one to run out
+        contract.set_upgrade_timelock(U64::from(MIN_UPGRADE_TIMELOCK));
+        assert_eq!(DEFAULT_UPGRADE_TIMELOCK, contract.get_upgrade_timelock().0);
+        let queued = contract.get_queued_upgrade_timelock().unwrap();
+        assert_eq!(11 + DEFAULT_UPGRADE_TIMELOCK, queued.effective_time);
+        contract.stage_upgrade(code_hash);
+        assert_eq!(11 + DEFAULT_UPGRADE_TIMELOCK, contract.get_staged_upgrade().unwrap().deployable_time);
+
+        context.block_timestamp = 11 + DEFAULT_UPGRADE_TIMELOCK;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        assert_eq!(MIN_UPGRADE_TIMELOCK, contract.get_upgrade_timelock().0);
+        contract.stage_upgrade(code_hash);
+        assert!(contract.get_queued_upgrade_timelock().is_none());
+        assert_eq!(11 + DEFAULT_UPGRADE_TIMELOCK + MIN_UPGRADE_TIMELOCK, contract.get_staged_upgrade().unwrap().deployable_time);
+
+        // A longer timelock is in force right away
+        contract.set_upgrade_timelock(U64::from(DEFAULT_UPGRADE_TIMELOCK));
+        assert_eq!(DEFAULT_UPGRADE_TIMELOCK, contract.get_upgrade_timelock().0);
+        assert!(contract.get_queued_upgrade_timelock().is_none());
+
+        // Once the approvers are set up, the timelock goes through a proposal
+        contract.set_multisig(vec![String::from("bob_near"), String::from("carol_near")], 2, None);
+        let proposal_id = contract.propose(ProposalAction::SetUpgradeTimelock { upgrade_timelock: U64::from(3 * DEFAULT_UPGRADE_TIMELOCK) });
+
+        context.predecessor_account_id = String::from("carol_near");
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.approve(proposal_id);
+        assert_eq!(3 * DEFAULT_UPGRADE_TIMELOCK, contract.get_upgrade_timelock().0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_UPGRADE_TIMELOCK_TOO_SHORT")]
+    fn set_upgrade_timelock_below_minimum() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.set_upgrade_timelock(U64::from(MIN_UPGRADE_TIMELOCK - 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MULTISIG_REQUIRED")]
+    fn set_upgrade_timelock_with_multisig() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.set_multisig(vec![String::from("bob_near"), String::from("carol_near")], 2, None);
+
+        contract.set_upgrade_timelock(U64::from(DEFAULT_UPGRADE_TIMELOCK));
+    }
+
+    #[test]
+    fn paginated_views() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.buy_ticket([1, 3, 4, 5, 6, 7]);
+        contract.buy_ticket([1, 4, 5, 6, 7, 8]);
+
+        let summaries = contract.get_jackpot_summaries(None, None);
+        assert_eq!(1, summaries.len());
+        assert_eq!(3, summaries[0].no_of_tickets);
+        assert!(contract.get_jackpot_summaries(Some(1), None).is_empty());
+
+        // The jackpot view leaves the tickets to the paginated views
+        assert_eq!(3, contract.get_jackpot(1).unwrap().no_of_tickets);
+        assert!(serde_json::to_value(contract.get_jackpot(1).unwrap()).unwrap().get("ticketIds").is_none());
+        assert!(contract.get_jackpot(0).is_none());
+        assert!(contract.get_jackpot(2).is_none());
+        assert_eq!(vec![2, 3], contract.get_jackpot_ticket_ids(1, Some(1), Some(5)));
+        assert_eq!(MAX_PAGE_LIMIT, get_page_range(1_000, None, Some(u64::MAX)).count() as u64);
+
+        let account_id = String::from("bob_near");
+        let tickets = contract.get_account_tickets_paged(&account_id, Some(1), Some(1));
+        assert_eq!(1, tickets.len());
+        assert_eq!([1, 3, 4, 5, 6, 7], tickets[0].picked_numbers);
+        assert_eq!(vec![account_id], contract.get_account_ids(None, None));
+
+        assert_eq!(2, contract.get_tickets(Some(1), None).len());
+        assert_eq!([1, 4, 5, 6, 7, 8], contract.get_ticket(3).unwrap().picked_numbers);
+        assert!(contract.get_ticket(4).is_none());
+    }
+
+    #[test]
+    fn event_logs() {
+        let mut context = get_context(vec![], false);
+        context.block_timestamp = BLOCK_TIMESTAMP;
+        testing_env!(context);
+
+        let mut contract = Lottery::new(String::from("bob_near"));
+        contract.config.game_type = GameType::GuaranteedWinner;
+        contract.create_jackpot();
+        contract.storage_deposit(None, None);
+        contract.deposit();
+        contract.buy_ticket([1, 2, 3, 4, 5, 6]);
+        contract.draw_jackpot();
+        contract.settle_jackpot(None);
+        contract.claim_prize(1);
+
+        let events: Vec<serde_json::Value> = get_logs().iter()
+            .filter_map(|log| log.strip_prefix("EVENT_JSON:"))
+            .map(|log| serde_json::from_str(log).unwrap())
+            .collect();
+
+        let names: Vec<&str> = events.iter().map(|event| event["event"].as_str().unwrap()).collect();
+        assert_eq!(vec!["jackpot_created", "deposit", "ticket_bought", "draw_result", "win_tickets_found", "jackpot_settled", "winner_paid"], names);
+        assert_eq!(serde_json::json!([1]), events[4]["data"][0]["ticket_ids"]);
+        assert_eq!(1, events[5]["data"][0]["no_of_win_tickets"]);
+
+        let ticket_bought = &events[2];
+        assert_eq!("lottery", ticket_bought["standard"]);
+        assert_eq!("2.0.0", ticket_bought["version"]);
+        assert_eq!(1, ticket_bought["data"][0]["ticket_id"]);
+        assert_eq!(1, ticket_bought["data"][0]["jackpot_id"]);
+        assert_eq!(ONE_NEAR.to_string(), ticket_bought["data"][0]["amount"]);
+        assert_eq!(serde_json::json!([1, 2, 3, 4, 5, 6]), ticket_bought["data"][0]["picked_numbers"]);
     }
 }
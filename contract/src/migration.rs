@@ -0,0 +1,412 @@
+use crate::*;
+
+// Every layout the contract state has been stored with. Version 0 is the first release, a change
+// to a stored struct adds a version below with its conversion to the latest layout.
+pub const STATE_VERSION: u32 = 1;
+
+// Storage key of the version the state was written with, next to the state itself. The first
+// release didn't record it, a state without it is stored with the layout of version 0.
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+
+// Layout of the contract state stored by the first release
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct LotteryV0 {
+    pub owner_id: AccountId,
+    pub account_infoes: UnorderedMap<AccountId, AccountInfo>,
+    pub jackpots: Vector<Jackpot>,
+    pub tickets: UnorderedMap<TicketId, Ticket>,
+}
+
+// Read once by `migrate`, the size of its variants doesn't matter
+#[allow(clippy::large_enum_variant)]
+pub enum VersionedLottery {
+    V0(LotteryV0),
+    V1(Lottery),
+}
+
+impl VersionedLottery {
+    /// Read the stored state with the layout of its recorded version.
+    pub fn read(raw: &[u8]) -> Self {
+        let version = env::storage_read(STATE_VERSION_KEY).map(|version| u32::try_from_slice(&version).unwrap()).unwrap_or(0);
+        Self::from_versioned_slice(version, raw)
+    }
+
+    /// Record the version the state is written with.
+    pub fn write_version() {
+        env::storage_write(STATE_VERSION_KEY, &STATE_VERSION.try_to_vec().unwrap());
+    }
+
+    pub fn from_versioned_slice(version: u32, raw: &[u8]) -> Self {
+        let state = match version {
+            0 => LotteryV0::try_from_slice(raw).map(VersionedLottery::V0),
+            1 => Lottery::try_from_slice(raw).map(VersionedLottery::V1),
+            _ => panic!("{}", LotteryError::UnknownLayout),
+        };
+
+        state.unwrap_or_else(|_| panic!("{}", LotteryError::UnknownLayout))
+    }
+
+    pub fn version(&self) -> u32 {
+        match self {
+            VersionedLottery::V0(_) => 0,
+            VersionedLottery::V1(_) => 1,
+        }
+    }
+}
+
+impl From<VersionedLottery> for Lottery {
+    fn from(lottery: VersionedLottery) -> Self {
+        match lottery {
+            // The registration cost and the funds are counted by `migrate`
+            VersionedLottery::V0(lottery) => Self {
+                owner_id: lottery.owner_id,
+                account_infoes: lottery.account_infoes,
+                jackpots: lottery.jackpots,
+                tickets: lottery.tickets,
+                rollover_index: 0,
+                upgrade_timelock: DEFAULT_UPGRADE_TIMELOCK,
+                staged_upgrade: Option::None,
+                account_storage_usage: 0,
+                roles: UnorderedMap::new(StorageKey::Roles),
+                proposed_owner_id: Option::None,
+                paused: false,
+                wind_down: Option::None,
+                multisig: Option::None,
+                proposals: UnorderedMap::new(StorageKey::Proposals),
+                proposal_count: 0,
+                config: JackpotConfig::default(),
+                queued_config: Option::None,
+                audit_log: Vector::new(StorageKey::AuditLog),
+                treasury: 0,
+                fund_totals: FundTotals::default(),
+                queued_upgrade_timelock: Option::None,
+            },
+            VersionedLottery::V1(lottery) => lottery,
+        }
+    }
+}
+
+pub enum VersionedJackpot {
+    V0(JackpotV0),
+    V1(Box<Jackpot>),
+}
+
+impl VersionedJackpot {
+    pub fn from_slice(raw: &[u8]) -> Self {
+        if let Ok(jackpot) = Jackpot::try_from_slice(raw) {
+            return VersionedJackpot::V1(Box::new(jackpot));
+        }
+
+        match JackpotV0::try_from_slice(raw) {
+            Ok(jackpot) => VersionedJackpot::V0(jackpot),
+            Err(_) => panic!("{}", LotteryError::UnknownLayout),
+        }
+    }
+
+    pub fn id(&self) -> JackpotId {
+        match self {
+            VersionedJackpot::V0(jackpot) => jackpot.id,
+            VersionedJackpot::V1(jackpot) => jackpot.id,
+        }
+    }
+
+    pub fn ticket_ids(&self) -> &Vector<TicketId> {
+        match self {
+            VersionedJackpot::V0(jackpot) => &jackpot.ticket_ids,
+            VersionedJackpot::V1(jackpot) => &jackpot.ticket_ids,
+        }
+    }
+
+    pub fn locked_amount(&self) -> Balance {
+        match self {
+            VersionedJackpot::V0(jackpot) => jackpot.locked_amount,
+            VersionedJackpot::V1(jackpot) => jackpot.locked_amount,
+        }
+    }
+}
+
+pub enum VersionedTicket {
+    V0(TicketV0),
+    V1(Ticket),
+}
+
+impl VersionedTicket {
+    pub fn from_slice(raw: &[u8]) -> Self {
+        if let Ok(ticket) = Ticket::try_from_slice(raw) {
+            return VersionedTicket::V1(ticket);
+        }
+
+        match TicketV0::try_from_slice(raw) {
+            Ok(ticket) => VersionedTicket::V0(ticket),
+            Err(_) => panic!("{}", LotteryError::UnknownLayout),
+        }
+    }
+}
+
+pub enum VersionedDrawingResult {
+    V0(DrawingResultV0),
+    V1(DrawingResult),
+}
+
+impl VersionedDrawingResult {
+    pub fn from_slice(raw: &[u8]) -> Self {
+        if let Ok(result) = DrawingResult::try_from_slice(raw) {
+            return VersionedDrawingResult::V1(result);
+        }
+
+        match DrawingResultV0::try_from_slice(raw) {
+            Ok(result) => VersionedDrawingResult::V0(result),
+            Err(_) => panic!("{}", LotteryError::UnknownLayout),
+        }
+    }
+}
+
+pub enum VersionedAccountInfo {
+    V0(AccountInfoV0),
+    V1(AccountInfo),
+}
+
+impl VersionedAccountInfo {
+    pub fn from_slice(raw: &[u8]) -> Self {
+        if let Ok(account_info) = AccountInfo::try_from_slice(raw) {
+            return VersionedAccountInfo::V1(account_info);
+        }
+
+        match AccountInfoV0::try_from_slice(raw) {
+            Ok(account_info) => VersionedAccountInfo::V0(account_info),
+            Err(_) => panic!("{}", LotteryError::UnknownLayout),
+        }
+    }
+}
+
+// The collections created by the first release keep their ASCII prefixes ("ta{account_id}",
+// "tj{id}", "tjw{id}", "dr{id}"), the prefix is stored inside each collection so they keep
+// working. `migrate_account_storage` and `migrate_jackpot_storage` move
+// them under the `StorageKey` prefixes. The top level collections are left where they are, their
+// prefixes can't collide with any `StorageKey` one.
+
+#[near_bindgen]
+impl Lottery {
+    /// Upgrade the state stored by any previous release, called by the contract itself right
+    /// after deploying. The collections are then upgraded in batches, in this order:
+    /// `migrate_tickets`, `migrate_jackpots`, `migrate_drawed_results` of each jackpot and
+    /// `migrate_accounts`.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let raw_state = env::storage_read(b"STATE").unwrap_or_else(|| panic!("{}", LotteryError::NotInitialized));
+        let state = VersionedLottery::read(&raw_state);
+
+        env::log(format!("Migrating the state from version {} to {}", state.version(), STATE_VERSION).as_bytes());
+
+        let has_fund_totals = state.version() >= 1;
+        let mut lottery = Self::from(state);
+        lottery.measure_account_storage_usage();
+        if !has_fund_totals {
+            lottery.count_fund_totals();
+        }
+        VersionedLottery::write_version();
+
+        lottery
+    }
+
+    /// Rewrite the tickets stored with a previous layout, starting at `from_index`.
+    /// Returns the number of migrated tickets.
+    pub fn migrate_tickets(&mut self, from_index: u64, limit: u64) -> u64 {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+
+        let to_index = std::cmp::min(from_index + limit, self.tickets.len());
+        let mut no_of_migrated = 0;
+        for i in from_index..to_index {
+            let raw_ticket = self.tickets.values_as_vector().get_raw(i).unwrap();
+            let ticket = match VersionedTicket::from_slice(&raw_ticket) {
+                VersionedTicket::V0(ticket) => {
+                    let jackpot_id = self.find_ticket_jackpot_id(ticket.id);
+                    ticket.into_ticket(jackpot_id)
+                },
+                VersionedTicket::V1(_) => continue,
+            };
+
+            // Write the raw value, the typed insert would deserialize the legacy one
+            let raw_ticket_id = self.tickets.keys_as_vector().get_raw(i).unwrap();
+            self.tickets.insert_raw(&raw_ticket_id, &ticket.try_to_vec().unwrap());
+            no_of_migrated += 1;
+        }
+
+        no_of_migrated
+    }
+
+    /// Rewrite the jackpots stored with a previous layout, starting at `from_index`. The tickets
+    /// must have been migrated first, they are indexed by numbers for the open jackpot.
+    /// Returns the number of migrated jackpots.
+    pub fn migrate_jackpots(&mut self, from_index: u64, limit: u64) -> u64 {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+
+        let to_index = std::cmp::min(from_index + limit, self.jackpots.len());
+        let mut no_of_migrated = 0;
+        for i in from_index..to_index {
+            let raw_jackpot = self.jackpots.get_raw(i).unwrap();
+            let jackpot = match VersionedJackpot::from_slice(&raw_jackpot) {
+                VersionedJackpot::V0(legacy_jackpot) => {
+                    // Closed jackpots are never settled again, only the open one needs the index.
+                    // Its tickets are indexed in batches by `migrate_ticket_index`
+                    let is_open = legacy_jackpot.end_time.is_none();
+                    let mut jackpot = Jackpot::from(legacy_jackpot);
+                    if is_open && !jackpot.ticket_ids.is_empty() {
+                        jackpot.index_cursor = Some(0);
+                    }
+                    jackpot
+                },
+                VersionedJackpot::V1(_) => continue,
+            };
+
+            self.jackpots.replace_raw(i, &jackpot.try_to_vec().unwrap());
+            no_of_migrated += 1;
+        }
+
+        no_of_migrated
+    }
+
+    /// Index the next `limit` tickets of a jackpot migrated from the first layout by their numbers.
+    /// Its tickets can't be bought or drawn until then. Returns true once every ticket is indexed.
+    pub fn migrate_ticket_index(&mut self, jackpot_id: JackpotId, limit: u64) -> bool {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+
+        let mut jackpot = self.internal_get_jackpot(jackpot_id).unwrap_or_else(|| panic!("{}", LotteryError::JackpotNotFound));
+        let is_indexed = jackpot.index_next_tickets(&self.tickets, limit);
+        self.update_jackpot(&jackpot);
+
+        is_indexed
+    }
+
+    /// Rewrite the drawing results of a jackpot stored with the first layout.
+    /// Returns the number of migrated results.
+    pub fn migrate_drawed_results(&mut self, jackpot_id: JackpotId) -> u64 {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+
+        let jackpot = self.internal_get_jackpot(jackpot_id);
+        assert!(jackpot.is_some(), "{}", LotteryError::JackpotNotFound);
+
+        let mut drawed_results = jackpot.unwrap().drawed_results;
+        let mut no_of_migrated = 0;
+        for i in 0..drawed_results.len() {
+            let raw_result = drawed_results.get_raw(i).unwrap();
+            let result = match VersionedDrawingResult::from_slice(&raw_result) {
+                VersionedDrawingResult::V0(result) => DrawingResult::from(result),
+                VersionedDrawingResult::V1(_) => continue,
+            };
+
+            drawed_results.replace_raw(i, &result.try_to_vec().unwrap());
+            no_of_migrated += 1;
+        }
+
+        no_of_migrated
+    }
+
+    /// Rewrite the accounts stored with a previous layout, starting at `from_index`.
+    /// Returns the number of migrated accounts.
+    pub fn migrate_accounts(&mut self, from_index: u64, limit: u64) -> u64 {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+
+        let to_index = std::cmp::min(from_index + limit, self.account_infoes.len());
+        let mut no_of_migrated = 0;
+        for i in from_index..to_index {
+            let raw_account_info = self.account_infoes.values_as_vector().get_raw(i).unwrap();
+            let account_info = match VersionedAccountInfo::from_slice(&raw_account_info) {
+                VersionedAccountInfo::V0(account_info) => AccountInfo::from(account_info),
+                VersionedAccountInfo::V1(_) => continue,
+            };
+
+            let raw_account_id = self.account_infoes.keys_as_vector().get_raw(i).unwrap();
+            self.account_infoes.insert_raw(&raw_account_id, &account_info.try_to_vec().unwrap());
+            no_of_migrated += 1;
+        }
+
+        no_of_migrated
+    }
+
+    /// Move the ticket ids of the accounts stored under a legacy prefix, starting at `from_index`.
+    /// The accounts must have been migrated to the latest layout first.
+    /// Returns the number of migrated accounts.
+    pub fn migrate_account_storage(&mut self, from_index: u64, limit: u64) -> u64 {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+
+        let to_index = std::cmp::min(from_index + limit, self.account_infoes.len());
+        let mut no_of_migrated = 0;
+        for i in from_index..to_index {
+            let account_id = self.account_infoes.keys_as_vector().get(i).unwrap();
+            let mut account_info = self.account_infoes.get(&account_id).unwrap();
+            if account_info.move_to_storage_key(&account_id) {
+                self.account_infoes.insert(&account_id, &account_info);
+                no_of_migrated += 1;
+            }
+        }
+
+        no_of_migrated
+    }
+
+    /// Move the next `limit` elements of the collections of a jackpot stored under legacy
+    /// prefixes. The jackpot must have been migrated to the latest layout and its tickets
+    /// indexed first. Returns true once every collection is under its `StorageKey` prefix.
+    pub fn migrate_jackpot_storage(&mut self, jackpot_id: JackpotId, limit: u64) -> bool {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+
+        let mut jackpot = self.internal_get_jackpot(jackpot_id).unwrap_or_else(|| panic!("{}", LotteryError::JackpotNotFound));
+        assert!(jackpot.index_cursor.is_none(), "{}", LotteryError::TicketsNotIndexed);
+
+        let is_moved = jackpot.move_to_storage_keys(limit);
+        self.update_jackpot(&jackpot);
+
+        is_moved
+    }
+
+    // Add up the funds once, they are kept up to date from then on. The collections may still
+    // hold legacy layouts, they are read raw.
+    fn count_fund_totals(&mut self) {
+        let mut fund_totals = FundTotals::default();
+        for raw_account_info in self.account_infoes.values_as_vector().iter_raw() {
+            match VersionedAccountInfo::from_slice(&raw_account_info) {
+                VersionedAccountInfo::V0(account_info) => fund_totals.player_balance += account_info.balance,
+                VersionedAccountInfo::V1(account_info) => {
+                    fund_totals.player_balance += account_info.balance;
+                    fund_totals.storage_deposit += account_info.storage_deposit;
+                },
+            }
+        }
+        for raw_jackpot in self.jackpots.iter_raw() {
+            fund_totals.locked_amount += VersionedJackpot::from_slice(&raw_jackpot).locked_amount();
+        }
+        for proposal in self.proposals.values() {
+            fund_totals.proposal_deposit += proposal.deposit;
+        }
+
+        self.fund_totals = fund_totals;
+    }
+
+    // Tickets of the first release don't know their jackpot, each jackpot holds a range of ids
+    fn find_ticket_jackpot_id(&self, ticket_id: TicketId) -> JackpotId {
+        for raw_jackpot in self.jackpots.iter_raw() {
+            let jackpot = VersionedJackpot::from_slice(&raw_jackpot);
+            let ticket_ids = jackpot.ticket_ids();
+            if !ticket_ids.is_empty() && ticket_ids.get(0).unwrap() <= ticket_id && ticket_id <= ticket_ids.get(ticket_ids.len() - 1).unwrap() {
+                return jackpot.id();
+            }
+        }
+
+        panic!("{}", LotteryError::JackpotNotFound)
+    }
+}
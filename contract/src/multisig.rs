@@ -0,0 +1,290 @@
+use crate::*;
+use serde::ser::SerializeStructVariant;
+
+// 7 days in nanoseconds
+const DEFAULT_PROPOSAL_DURATION: Timestamp = 7 * 24 * 60 * 60 * 1_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Multisig {
+    pub approvers: Vec<AccountId>,
+    // Number of approvals a proposal needs before its action runs
+    pub threshold: u32,
+    // How long a proposal can be approved
+    pub proposal_duration: Timestamp,
+}
+
+impl Serialize for Multisig {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Multisig", 3)?;
+        state.serialize_field("approvers", &self.approvers)?;
+        state.serialize_field("threshold", &self.threshold)?;
+        state.serialize_field("proposalDuration", &self.proposal_duration)?;
+        state.end()
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize)]
+pub enum ProposalAction {
+    // Seeded with the deposit attached to the proposal
+    CreateJackpot,
+    GrantRole { role: Role, account_id: AccountId },
+    RevokeRole { role: Role, account_id: AccountId },
+    ProposeOwner { owner_id: AccountId },
+    StageUpgrade { code_hash: Base58CryptoHash },
+    SetMultisig { approvers: Vec<AccountId>, threshold: u32 },
+    // Paid to the owner, the whole treasury without `amount`
+    WithdrawTreasury { amount: Option<U128> },
+    SetUpgradeTimelock { upgrade_timelock: U64 },
+    ScheduleConfig { ticket_price: U128, claim_duration: U64, game_type: GameType, fee_bps: u32, effective_time: U64 },
+    StartWindDown,
+}
+
+// Written with the field names `propose` takes
+impl Serialize for ProposalAction {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            ProposalAction::CreateJackpot => serializer.serialize_unit_variant("ProposalAction", 0, "CreateJackpot"),
+            ProposalAction::GrantRole { role, account_id } => {
+                let mut state = serializer.serialize_struct_variant("ProposalAction", 1, "GrantRole", 2)?;
+                state.serialize_field("role", role)?;
+                state.serialize_field("account_id", account_id)?;
+                state.end()
+            },
+            ProposalAction::RevokeRole { role, account_id } => {
+                let mut state = serializer.serialize_struct_variant("ProposalAction", 2, "RevokeRole", 2)?;
+                state.serialize_field("role", role)?;
+                state.serialize_field("account_id", account_id)?;
+                state.end()
+            },
+            ProposalAction::ProposeOwner { owner_id } => {
+                let mut state = serializer.serialize_struct_variant("ProposalAction", 3, "ProposeOwner", 1)?;
+                state.serialize_field("owner_id", owner_id)?;
+                state.end()
+            },
+            ProposalAction::StageUpgrade { code_hash } => {
+                let mut state = serializer.serialize_struct_variant("ProposalAction", 4, "StageUpgrade", 1)?;
+                state.serialize_field("code_hash", code_hash)?;
+                state.end()
+            },
+            ProposalAction::SetMultisig { approvers, threshold } => {
+                let mut state = serializer.serialize_struct_variant("ProposalAction", 5, "SetMultisig", 2)?;
+                state.serialize_field("approvers", approvers)?;
+                state.serialize_field("threshold", threshold)?;
+                state.end()
+            },
+            ProposalAction::WithdrawTreasury { amount } => {
+                let mut state = serializer.serialize_struct_variant("ProposalAction", 6, "WithdrawTreasury", 1)?;
+                state.serialize_field("amount", amount)?;
+                state.end()
+            },
+            ProposalAction::SetUpgradeTimelock { upgrade_timelock } => {
+                let mut state = serializer.serialize_struct_variant("ProposalAction", 7, "SetUpgradeTimelock", 1)?;
+                state.serialize_field("upgrade_timelock", upgrade_timelock)?;
+                state.end()
+            },
+            ProposalAction::ScheduleConfig { ticket_price, claim_duration, game_type, fee_bps, effective_time } => {
+                let mut state = serializer.serialize_struct_variant("ProposalAction", 8, "ScheduleConfig", 5)?;
+                state.serialize_field("ticket_price", ticket_price)?;
+                state.serialize_field("claim_duration", claim_duration)?;
+                state.serialize_field("game_type", game_type)?;
+                state.serialize_field("fee_bps", fee_bps)?;
+                state.serialize_field("effective_time", effective_time)?;
+                state.end()
+            },
+            ProposalAction::StartWindDown => serializer.serialize_unit_variant("ProposalAction", 9, "StartWindDown"),
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Proposal {
+    pub id: ProposalId,
+    pub proposer_id: AccountId,
+    pub action: ProposalAction,
+    // Attached by the proposer, refunded if the proposal is removed
+    pub deposit: Balance,
+    pub approvals: Vec<AccountId>,
+    pub expiry_time: Timestamp,
+}
+
+impl Proposal {
+    pub fn is_expired(&self) -> bool {
+        get_time_now() > self.expiry_time
+    }
+}
+
+impl Serialize for Proposal {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Proposal", 7)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("proposerId", &self.proposer_id)?;
+        state.serialize_field("action", &self.action)?;
+        state.serialize_field("deposit", &self.deposit.to_string())?;
+        state.serialize_field("approvals", &self.approvals)?;
+        state.serialize_field("expiryTime", &self.expiry_time)?;
+        state.serialize_field("expired", &self.is_expired())?;
+        state.end()
+    }
+}
+
+// Once the approvers are set up, the seeded jackpots, the role changes, the ownership proposals, the
+// upgrades, their timelock, the treasury withdrawals, the parameter changes and the wind down can
+// only go through a proposal approved by `threshold` of them.
+#[near_bindgen]
+impl Lottery {
+    pub fn get_multisig(&self) -> Option<&Multisig> {
+        self.multisig.as_ref()
+    }
+
+    pub fn get_proposal(&self, proposal_id: ProposalId) -> Option<Proposal> {
+        self.proposals.get(&proposal_id)
+    }
+
+    /// The proposals waiting for approvals, the expired ones are listed until they are removed.
+    pub fn get_proposals(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<Proposal> {
+        let proposals = self.proposals.values_as_vector();
+
+        get_page_range(proposals.len(), from_index, limit)
+            .map(|i| proposals.get(i).unwrap())
+            .collect()
+    }
+
+    /// Set up the approvers, only once. They are then changed through a `SetMultisig` proposal.
+    pub fn set_multisig(&mut self, approvers: Vec<AccountId>, threshold: u32, proposal_duration: Option<U64>) {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+        self.assert_no_multisig();
+
+        let proposal_duration = proposal_duration.map(|duration| duration.into()).unwrap_or(DEFAULT_PROPOSAL_DURATION);
+        self.internal_set_multisig(approvers, threshold, proposal_duration);
+    }
+
+    /// Propose an action, the proposer approves it right away. The deposit attached to a
+    /// `CreateJackpot` proposal seeds the jackpot.
+    #[payable]
+    pub fn propose(&mut self, action: ProposalAction) -> ProposalId {
+        let account_id = env::predecessor_account_id();
+        let multisig = self.multisig.as_ref().unwrap_or_else(|| panic!("{}", LotteryError::NoMultisig));
+        assert!(multisig.approvers.contains(&account_id), "{}", LotteryError::NotApprover);
+
+        let deposit = env::attached_deposit();
+        assert!(deposit == 0 || matches!(action, ProposalAction::CreateJackpot), "{}", LotteryError::UnexpectedDeposit);
+
+        self.proposal_count += 1;
+        let proposal = Proposal {
+            id: self.proposal_count,
+            proposer_id: account_id.clone(),
+            action,
+            deposit,
+            approvals: Vec::new(),
+            expiry_time: get_time_now() + multisig.proposal_duration,
+        };
+        log_proposal_created(&proposal);
+        self.fund_totals.proposal_deposit += deposit;
+
+        let proposal_id = proposal.id;
+        self.approve_proposal(proposal, account_id);
+
+        proposal_id
+    }
+
+    /// Approve a proposal, the approval which reaches the threshold runs its action.
+    /// Returns true when the action has run.
+    pub fn approve(&mut self, proposal_id: ProposalId) -> bool {
+        let account_id = env::predecessor_account_id();
+        let multisig = self.multisig.as_ref().unwrap_or_else(|| panic!("{}", LotteryError::NoMultisig));
+        assert!(multisig.approvers.contains(&account_id), "{}", LotteryError::NotApprover);
+
+        let proposal = self.proposals.get(&proposal_id).unwrap_or_else(|| panic!("{}", LotteryError::ProposalNotFound));
+        assert!(!proposal.is_expired(), "{}", LotteryError::ProposalExpired);
+        assert!(!proposal.approvals.contains(&account_id), "{}", LotteryError::AlreadyApproved);
+
+        self.approve_proposal(proposal, account_id)
+    }
+
+    /// Drop a proposal and refund its deposit. The proposer can remove it at any time, anyone
+    /// once it has expired.
+    pub fn remove_proposal(&mut self, proposal_id: ProposalId) {
+        let proposal = self.proposals.get(&proposal_id).unwrap_or_else(|| panic!("{}", LotteryError::ProposalNotFound));
+        assert!(proposal.proposer_id == env::predecessor_account_id() || proposal.is_expired(), "{}", LotteryError::NotProposer);
+
+        self.proposals.remove(&proposal_id);
+        self.fund_totals.proposal_deposit -= proposal.deposit;
+        log_proposal_removed(proposal_id);
+
+        if proposal.deposit > 0 {
+            Promise::new(proposal.proposer_id).transfer(proposal.deposit);
+        }
+    }
+}
+
+impl Lottery {
+    pub(crate) fn assert_no_multisig(&self) {
+        assert!(self.multisig.is_none(), "{}", LotteryError::MultisigRequired);
+    }
+
+    fn internal_set_multisig(&mut self, approvers: Vec<AccountId>, threshold: u32, proposal_duration: Timestamp) {
+        for (i, approver_id) in approvers.iter().enumerate() {
+            assert!(env::is_valid_account_id(approver_id.as_bytes()), "{}", LotteryError::InvalidAccountId);
+            assert!(!approvers[..i].contains(approver_id), "{}", LotteryError::InvalidMultisig);
+        }
+        assert!(threshold > 0 && threshold as usize <= approvers.len(), "{}", LotteryError::InvalidMultisig);
+
+        let multisig = Multisig { approvers, threshold, proposal_duration };
+        log_multisig_changed(&multisig);
+        self.record_audit(AuditAction::MultisigChanged { approvers: multisig.approvers.clone(), threshold });
+        self.multisig = Some(multisig);
+    }
+
+    // Record the approval, and run the action once enough approvers have agreed
+    fn approve_proposal(&mut self, mut proposal: Proposal, account_id: AccountId) -> bool {
+        proposal.approvals.push(account_id.clone());
+        log_proposal_approved(proposal.id, &account_id);
+
+        // Approvals of accounts which are no longer approvers don't count
+        let multisig = self.multisig.as_ref().unwrap();
+        let no_of_approvals = proposal.approvals.iter().filter(|id| multisig.approvers.contains(id)).count();
+        if no_of_approvals < multisig.threshold as usize {
+            self.proposals.insert(&proposal.id, &proposal);
+            return false;
+        }
+
+        self.proposals.remove(&proposal.id);
+        self.fund_totals.proposal_deposit -= proposal.deposit;
+        log_proposal_executed(proposal.id);
+        self.record_audit(AuditAction::ProposalExecuted { proposal_id: proposal.id });
+
+        match proposal.action {
+            ProposalAction::CreateJackpot => self.internal_create_jackpot(proposal.deposit),
+            ProposalAction::GrantRole { role, account_id } => self.internal_grant_role(role, account_id),
+            ProposalAction::RevokeRole { role, account_id } => self.internal_revoke_role(role, account_id),
+            ProposalAction::ProposeOwner { owner_id } => self.internal_propose_owner(owner_id),
+            ProposalAction::StageUpgrade { code_hash } => self.internal_stage_upgrade(code_hash),
+            ProposalAction::SetMultisig { approvers, threshold } => {
+                let proposal_duration = self.multisig.as_ref().unwrap().proposal_duration;
+                self.internal_set_multisig(approvers, threshold, proposal_duration);
+            },
+            ProposalAction::WithdrawTreasury { amount } => {
+                let owner_id = self.owner_id.clone();
+                self.internal_withdraw_treasury(amount.map(|amount| amount.into()), owner_id);
+            },
+            ProposalAction::SetUpgradeTimelock { upgrade_timelock } => self.internal_set_upgrade_timelock(upgrade_timelock.into()),
+            ProposalAction::ScheduleConfig { ticket_price, claim_duration, game_type, fee_bps, effective_time } => {
+                self.internal_schedule_config(ticket_price.into(), claim_duration.into(), game_type, fee_bps, effective_time.into());
+            },
+            ProposalAction::StartWindDown => self.internal_start_wind_down(),
+        }
+
+        true
+    }
+}
@@ -0,0 +1,52 @@
+use crate::*;
+
+// The ownership is handed over in two steps, the proposed owner must accept it. An account id
+// mistyped in the proposal can't take the contract, the proposal is just cancelled.
+#[near_bindgen]
+impl Lottery {
+    pub fn get_proposed_owner_id(&self) -> Option<AccountId> {
+        self.proposed_owner_id.clone()
+    }
+
+    /// Propose a new owner, it replaces any pending proposal.
+    pub fn propose_owner(&mut self, owner_id: AccountId) {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+        self.assert_no_multisig();
+
+        self.internal_propose_owner(owner_id);
+    }
+
+    pub fn cancel_ownership_proposal(&mut self) {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+
+        let proposed_owner_id = self.proposed_owner_id.take().unwrap_or_else(|| panic!("{}", LotteryError::NoProposedOwner));
+        log_ownership_proposal_cancelled(&proposed_owner_id);
+        self.record_audit(AuditAction::OwnershipProposalCancelled { owner_id: proposed_owner_id });
+    }
+
+    /// Called by the proposed owner to take over the contract.
+    pub fn accept_ownership(&mut self) {
+        let account_id = env::predecessor_account_id();
+        let proposed_owner_id = self.proposed_owner_id.as_ref().unwrap_or_else(|| panic!("{}", LotteryError::NoProposedOwner));
+        assert!(account_id == *proposed_owner_id, "{}", LotteryError::NotProposedOwner);
+
+        log_owner_changed(&self.owner_id, &account_id);
+        self.record_audit(AuditAction::OwnerChanged { old_owner_id: self.owner_id.clone(), new_owner_id: account_id.clone() });
+        self.owner_id = account_id;
+        self.proposed_owner_id = None;
+    }
+}
+
+impl Lottery {
+    pub(crate) fn internal_propose_owner(&mut self, owner_id: AccountId) {
+        assert!(env::is_valid_account_id(owner_id.as_bytes()), "{}", LotteryError::InvalidAccountId);
+
+        log_ownership_proposed(&self.owner_id, &owner_id);
+        self.record_audit(AuditAction::OwnershipProposed { owner_id: owner_id.clone() });
+        self.proposed_owner_id = Some(owner_id);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::*;
+
+// A guardian can stop the contract, or the sales and the drawing of a single jackpot, as soon as
+// something goes wrong. Only the owner resumes them. Withdrawals and claims are never paused.
+#[near_bindgen]
+impl Lottery {
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Pause or resume deposits, jackpot creation, sales and drawing.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.assert_can_pause(paused);
+
+        self.paused = paused;
+        log_pause_changed(None, "all", paused);
+        self.record_audit(AuditAction::PauseChanged { jackpot_id: None, scope: String::from("all"), paused });
+    }
+
+    pub fn set_jackpot_sales_paused(&mut self, jackpot_id: JackpotId, paused: bool) {
+        self.assert_can_pause(paused);
+
+        let mut jackpot = self.internal_get_jackpot(jackpot_id).unwrap_or_else(|| panic!("{}", LotteryError::JackpotNotFound));
+        jackpot.sales_paused = paused;
+        self.update_jackpot(&jackpot);
+        log_pause_changed(Some(jackpot_id), "sales", paused);
+        self.record_audit(AuditAction::PauseChanged { jackpot_id: Some(jackpot_id), scope: String::from("sales"), paused });
+    }
+
+    pub fn set_jackpot_drawing_paused(&mut self, jackpot_id: JackpotId, paused: bool) {
+        self.assert_can_pause(paused);
+
+        let mut jackpot = self.internal_get_jackpot(jackpot_id).unwrap_or_else(|| panic!("{}", LotteryError::JackpotNotFound));
+        jackpot.drawing_paused = paused;
+        self.update_jackpot(&jackpot);
+        log_pause_changed(Some(jackpot_id), "drawing", paused);
+        self.record_audit(AuditAction::PauseChanged { jackpot_id: Some(jackpot_id), scope: String::from("drawing"), paused });
+    }
+}
+
+impl Lottery {
+    pub(crate) fn assert_not_paused(&self) {
+        assert!(!self.paused, "{}", LotteryError::ContractPaused);
+    }
+
+    fn assert_can_pause(&self, paused: bool) {
+        if paused {
+            self.assert_role(Role::Guardian);
+        }
+        else {
+            // Check account right (The caller must be the contract owner)
+            let account_id = env::predecessor_account_id();
+            assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    // Grants and revokes the roles
+    Admin,
+    // Creates and schedules the jackpots
+    Operator,
+    // Draws and settles the jackpots
+    Drawer,
+    // Withdraws the fees
+    Treasurer,
+    // Pauses the contract and the jackpots
+    Guardian,
+}
+
+impl Role {
+    pub const ALL: [Role; 5] = [Role::Admin, Role::Operator, Role::Drawer, Role::Treasurer, Role::Guardian];
+}
+
+impl Serialize for Role {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            Role::Admin => serializer.serialize_unit_variant("Role", 0, "Admin"),
+            Role::Operator => serializer.serialize_unit_variant("Role", 1, "Operator"),
+            Role::Drawer => serializer.serialize_unit_variant("Role", 2, "Drawer"),
+            Role::Treasurer => serializer.serialize_unit_variant("Role", 3, "Treasurer"),
+            Role::Guardian => serializer.serialize_unit_variant("Role", 4, "Guardian"),
+        }
+    }
+}
+
+// The owner holds every role, the other accounts only the roles granted to them
+#[near_bindgen]
+impl Lottery {
+    pub fn grant_role(&mut self, role: Role, account_id: AccountId) {
+        self.assert_role(Role::Admin);
+        self.assert_no_multisig();
+
+        self.internal_grant_role(role, account_id);
+    }
+
+    pub fn revoke_role(&mut self, role: Role, account_id: AccountId) {
+        self.assert_role(Role::Admin);
+        self.assert_no_multisig();
+
+        self.internal_revoke_role(role, account_id);
+    }
+
+    pub fn has_role(&self, role: Role, account_id: AccountId) -> bool {
+        account_id == self.owner_id || self.roles.get(&account_id).unwrap_or_default().contains(&role)
+    }
+
+    pub fn get_roles(&self, account_id: AccountId) -> Vec<Role> {
+        if account_id == self.owner_id {
+            return Role::ALL.to_vec();
+        }
+
+        self.roles.get(&account_id).unwrap_or_default()
+    }
+
+    /// The accounts the role has been granted to, besides the owner. The page is taken over
+    /// every account holding a role.
+    pub fn get_role_members(&self, role: Role, from_index: Option<u64>, limit: Option<u64>) -> Vec<AccountId> {
+        let account_ids = self.roles.keys_as_vector();
+        let roles = self.roles.values_as_vector();
+
+        get_page_range(account_ids.len(), from_index, limit)
+            .filter(|i| roles.get(*i).unwrap().contains(&role))
+            .map(|i| account_ids.get(i).unwrap())
+            .collect()
+    }
+}
+
+impl Lottery {
+    pub(crate) fn assert_role(&self, role: Role) {
+        assert!(self.has_role(role, env::predecessor_account_id()), "{}", LotteryError::MissingRole);
+    }
+
+    pub(crate) fn internal_grant_role(&mut self, role: Role, account_id: AccountId) {
+        assert!(env::is_valid_account_id(account_id.as_bytes()), "{}", LotteryError::InvalidAccountId);
+
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+        if !roles.contains(&role) {
+            roles.push(role);
+            self.roles.insert(&account_id, &roles);
+            log_role_granted(role, &account_id);
+            self.record_audit(AuditAction::RoleGranted { role, account_id });
+        }
+    }
+
+    pub(crate) fn internal_revoke_role(&mut self, role: Role, account_id: AccountId) {
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+        if roles.contains(&role) {
+            roles.retain(|r| *r != role);
+            if roles.is_empty() {
+                self.roles.remove(&account_id);
+            }
+            else {
+                self.roles.insert(&account_id, &roles);
+            }
+            log_role_revoked(role, &account_id);
+            self.record_audit(AuditAction::RoleRevoked { role, account_id });
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use crate::*;
+
+// Runtime of the lottery core backed by the NEAR environment
+pub(crate) struct NearRuntime;
+
+impl Clock for NearRuntime {
+    fn now(&self) -> u64 {
+        get_time_now()
+    }
+}
+
+impl Randomness for NearRuntime {
+    // The first 8 bytes of the block random seed, which the block producer can't pick like the
+    // block time. The drawing result records it for the verifier.
+    fn seed(&self) -> u64 {
+        let random_seed = env::random_seed();
+        u64::from_le_bytes(<[u8; 8]>::try_from(&random_seed[..8]).unwrap())
+    }
+}
+
+// Tickets of a jackpot, read from the contract collections
+pub(crate) struct JackpotTickets<'a> {
+    pub ticket_ids: &'a Vector<TicketId>,
+    pub tickets: &'a UnorderedMap<TicketId, Ticket>,
+}
+
+impl<'a> Storage for JackpotTickets<'a> {
+    fn no_of_tickets(&self) -> u64 {
+        self.ticket_ids.len()
+    }
+
+    fn get_picked_numbers(&self, index: u64) -> [u8; 6] {
+        let ticket_id = self.ticket_ids.get(index).unwrap();
+        self.tickets.get(&ticket_id).unwrap().picked_numbers
+    }
+}
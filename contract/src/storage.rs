@@ -0,0 +1,161 @@
+use crate::*;
+
+// Longest valid account id, the registration cost is measured with it
+const MAX_ACCOUNT_ID_LENGTH: usize = 64;
+
+// NEP-145 storage management, see https://nomicon.io/Standards/StorageManagement
+pub struct StorageBalance {
+    pub total: U128,
+    pub available: U128,
+}
+
+impl Serialize for StorageBalance {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("StorageBalance", 2)?;
+        state.serialize_field("total", &self.total)?;
+        state.serialize_field("available", &self.available)?;
+        state.end()
+    }
+}
+
+pub struct StorageBalanceBounds {
+    pub min: U128,
+    pub max: Option<U128>,
+}
+
+impl Serialize for StorageBalanceBounds {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("StorageBalanceBounds", 2)?;
+        state.serialize_field("min", &self.min)?;
+        state.serialize_field("max", &self.max)?;
+        state.end()
+    }
+}
+
+#[near_bindgen]
+impl Lottery {
+    /// Register an account, or top up its storage balance. Every ticket bought by the account is
+    /// paid from this balance. Without `account_id` the caller is registered.
+    #[payable]
+    pub fn storage_deposit(&mut self, account_id: Option<AccountId>, registration_only: Option<bool>) -> StorageBalance {
+        let amount = env::attached_deposit();
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        assert!(env::is_valid_account_id(account_id.as_bytes()), "{}", LotteryError::InvalidAccountId);
+
+        let registration_only = registration_only.unwrap_or(false);
+        let (account_info, refund) = match self.account_infoes.get(&account_id) {
+            Some(mut account_info) => {
+                if registration_only {
+                    (account_info, amount)
+                }
+                else {
+                    account_info.storage_deposit += amount;
+                    self.fund_totals.storage_deposit += amount;
+                    (account_info, 0)
+                }
+            },
+            None => {
+                let min_amount = self.storage_balance_bounds().min.0;
+                assert!(amount >= min_amount, "{}", LotteryError::InsufficientStorageBalance);
+
+                let storage_deposit = if registration_only { min_amount } else { amount };
+                let mut account_info = AccountInfo::new(account_id.clone());
+                account_info.storage_deposit = storage_deposit;
+                account_info.storage_usage = self.account_storage_usage;
+                self.fund_totals.storage_deposit += storage_deposit;
+                (account_info, amount - storage_deposit)
+            },
+        };
+        self.account_infoes.insert(&account_id, &account_info);
+
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+
+        account_info.get_storage_balance()
+    }
+
+    /// Withdraw the storage balance which is not used, all of it when `amount` is omitted.
+    #[payable]
+    pub fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert!(env::attached_deposit() == 1, "{}", LotteryError::OneYoctoRequired);
+
+        let account_id = env::predecessor_account_id();
+        let mut account_info = self.get_registered_account_info(&account_id);
+        let available = account_info.get_storage_balance().available.0;
+        let amount = amount.map(|amount| amount.0).unwrap_or(available);
+        assert!(amount <= available, "{}", LotteryError::InsufficientStorageBalance);
+
+        account_info.storage_deposit -= amount;
+        self.account_infoes.insert(&account_id, &account_info);
+        self.fund_totals.storage_deposit -= amount;
+
+        if amount > 0 {
+            Promise::new(account_id).transfer(amount);
+        }
+
+        account_info.get_storage_balance()
+    }
+
+    /// Remove the account and refund its storage deposit. The tickets of an account are kept
+    /// forever, so only an account without tickets can be unregistered. With `force` its
+    /// balance is refunded too. Returns false when the account is not registered.
+    #[payable]
+    pub fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        assert!(env::attached_deposit() == 1, "{}", LotteryError::OneYoctoRequired);
+
+        let account_id = env::predecessor_account_id();
+        let account_info = match self.account_infoes.get(&account_id) {
+            Some(account_info) => account_info,
+            None => return false,
+        };
+        assert!(account_info.ticket_ids.is_empty(), "{}", LotteryError::AccountHasTickets);
+        assert!(force.unwrap_or(false) || account_info.balance == 0, "{}", LotteryError::AccountHasBalance);
+
+        self.account_infoes.remove(&account_id);
+        self.fund_totals.player_balance -= account_info.balance;
+        self.fund_totals.storage_deposit -= account_info.storage_deposit;
+
+        if account_info.balance > 0 {
+            log_withdraw(&account_id, account_info.balance);
+        }
+        Promise::new(account_id).transfer(account_info.storage_deposit + account_info.balance);
+
+        true
+    }
+
+    /// The minimum is the cost of registering an account, there is no maximum as every ticket
+    /// uses more storage.
+    pub fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: (Balance::from(self.account_storage_usage) * env::storage_byte_cost()).into(),
+            max: Option::None,
+        }
+    }
+
+    pub fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.account_infoes.get(&account_id).map(|account_info| account_info.get_storage_balance())
+    }
+}
+
+impl Lottery {
+    pub(crate) fn get_registered_account_info(&self, account_id: &AccountId) -> AccountInfo {
+        self.account_infoes.get(account_id).unwrap_or_else(|| panic!("{}", LotteryError::AccountNotRegistered))
+    }
+
+    // Measure the storage used by registering the longest account id. Upper case letters are not
+    // valid in an account id, so the measured entry can't be the one of a registered account.
+    pub(crate) fn measure_account_storage_usage(&mut self) {
+        let initial_storage_usage = env::storage_usage();
+        let account_id = "A".repeat(MAX_ACCOUNT_ID_LENGTH);
+        self.account_infoes.insert(&account_id, &AccountInfo::new(account_id.clone()));
+        self.account_storage_usage = env::storage_usage() - initial_storage_usage;
+        self.account_infoes.remove(&account_id);
+    }
+}
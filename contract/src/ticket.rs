@@ -3,10 +3,35 @@ use crate::*;
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Ticket {
-    id: TicketId,
+    pub id: TicketId,
     pub account_id: AccountId,   
     pub picked_numbers: [u8; 6],
-    created_time: Timestamp
+    created_time: Timestamp,
+    pub jackpot_id: JackpotId,
+    // The picked numbers as bits, so matching numbers can be counted with a single AND
+    pub picked_mask: u64,
+}
+
+// Layout of tickets stored by the first release, before the jackpot id was added
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct TicketV0 {
+    pub id: TicketId,
+    pub account_id: AccountId,
+    pub picked_numbers: [u8; 6],
+    pub created_time: Timestamp,
+}
+
+impl TicketV0 {
+    pub fn into_ticket(self, jackpot_id: JackpotId) -> Ticket {
+        Ticket {
+            id: self.id,
+            account_id: self.account_id,
+            picked_numbers: self.picked_numbers,
+            created_time: self.created_time,
+            jackpot_id,
+            picked_mask: numbers_to_bitmask(&self.picked_numbers),
+        }
+    }
 }
 
 impl Serialize for Ticket {
@@ -14,22 +39,26 @@ impl Serialize for Ticket {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Ticket", 4)?;
+        let mut state = serializer.serialize_struct("Ticket", 6)?;
         state.serialize_field("id", &self.id)?;
         state.serialize_field("accountId", &self.account_id)?;
         state.serialize_field("pickedNumbers", &self.picked_numbers)?;
+        state.serialize_field("pickedMask", &U64::from(self.picked_mask))?;
         state.serialize_field("createdTime", &self.created_time)?;
+        state.serialize_field("jackpotId", &self.jackpot_id)?;
         state.end()
     }
 }
 
 impl Ticket {
-    pub fn new(id: &TicketId, account_id: &AccountId, picked_numbers: &[u8; 6]) -> Self {
+    pub fn new(id: &TicketId, account_id: &AccountId, picked_numbers: &[u8; 6], jackpot_id: JackpotId) -> Self {
         Self {
             id: *id,
             account_id: account_id.clone(),
             picked_numbers: *picked_numbers,
             created_time: get_time_now(),
+            jackpot_id,
+            picked_mask: numbers_to_bitmask(picked_numbers),
         }
     }
 }
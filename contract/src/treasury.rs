@@ -0,0 +1,94 @@
+use crate::*;
+
+// What the NEAR held by the contract is made of
+pub struct BalanceSheet {
+    // Owed to the players, they withdraw it at any time
+    pub player_balance: Balance,
+    pub storage_deposit: Balance,
+    // Pots of the jackpots, with the prizes which have not been claimed yet
+    pub locked_amount: Balance,
+    // Attached to the pending proposals, refunded if they are removed
+    pub proposal_deposit: Balance,
+    pub treasury: Balance,
+    pub contract_balance: Balance,
+}
+
+impl BalanceSheet {
+    pub fn get_total(&self) -> Balance {
+        self.player_balance + self.storage_deposit + self.locked_amount + self.proposal_deposit + self.treasury
+    }
+}
+
+// Running totals of the funds the contract holds for others, kept up to date wherever they move
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct FundTotals {
+    pub player_balance: Balance,
+    pub storage_deposit: Balance,
+    pub locked_amount: Balance,
+    pub proposal_deposit: Balance,
+}
+
+impl Serialize for BalanceSheet {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("BalanceSheet", 8)?;
+        state.serialize_field("playerBalance", &self.player_balance.to_string())?;
+        state.serialize_field("storageDeposit", &self.storage_deposit.to_string())?;
+        state.serialize_field("lockedAmount", &self.locked_amount.to_string())?;
+        state.serialize_field("proposalDeposit", &self.proposal_deposit.to_string())?;
+        state.serialize_field("treasury", &self.treasury.to_string())?;
+        state.serialize_field("total", &self.get_total().to_string())?;
+        state.serialize_field("contractBalance", &self.contract_balance.to_string())?;
+        // The contract balance over the total pays for the storage of the contract itself
+        state.serialize_field("solvent", &(self.contract_balance >= self.get_total()))?;
+        state.end()
+    }
+}
+
+// The fee of every ticket sold is kept apart from the players' funds, a treasurer withdraws it
+#[near_bindgen]
+impl Lottery {
+    pub fn get_treasury(&self) -> U128 {
+        self.treasury.into()
+    }
+
+    /// The funds the contract holds for the players, the jackpots, the proposals and the
+    /// treasury, next to its NEAR balance.
+    pub fn get_balance_sheet(&self) -> BalanceSheet {
+        BalanceSheet {
+            player_balance: self.fund_totals.player_balance,
+            storage_deposit: self.fund_totals.storage_deposit,
+            locked_amount: self.fund_totals.locked_amount,
+            proposal_deposit: self.fund_totals.proposal_deposit,
+            treasury: self.treasury,
+            contract_balance: env::account_balance(),
+        }
+    }
+
+    /// Transfer `amount` of the treasury to the caller, all of it when omitted. The fees of the open
+    /// jackpot stay until it closes, they are refunded if the lottery winds down.
+    pub fn withdraw_treasury(&mut self, amount: Option<U128>) {
+        // Check account right (The caller must be a treasurer)
+        self.assert_role(Role::Treasurer);
+        self.assert_no_multisig();
+
+        self.internal_withdraw_treasury(amount.map(|amount| amount.into()), env::predecessor_account_id());
+    }
+}
+
+impl Lottery {
+    pub(crate) fn internal_withdraw_treasury(&mut self, amount: Option<Balance>, receiver_id: AccountId) {
+        let available_amount = self.treasury - self.get_refundable_fees();
+        let amount = amount.unwrap_or(available_amount);
+        assert!(amount <= available_amount, "{}", LotteryError::InsufficientTreasury);
+        assert!(amount > 0, "{}", LotteryError::NothingToWithdraw);
+
+        self.treasury -= amount;
+        log_treasury_withdrawn(&receiver_id, amount);
+        self.record_audit(AuditAction::TreasuryWithdrawn { receiver_id: receiver_id.clone(), amount });
+
+        Promise::new(receiver_id).transfer(amount);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::*;
+
+// Gas attached to the migrate call which follows the deployment of an upgrade
+const GAS_FOR_MIGRATE: Gas = 50_000_000_000_000;
+// 1 day in nanoseconds
+pub const MIN_UPGRADE_TIMELOCK: Timestamp = 24 * 60 * 60 * 1_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct StagedUpgrade {
+    pub code_hash: CryptoHash,
+    pub staged_time: Timestamp,
+    // The code cannot be deployed before this time, players have until then to review it
+    pub deployable_time: Timestamp,
+}
+
+impl Serialize for StagedUpgrade {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("StagedUpgrade", 3)?;
+        state.serialize_field("codeHash", &Base58CryptoHash::from(self.code_hash))?;
+        state.serialize_field("stagedTime", &self.staged_time)?;
+        state.serialize_field("deployableTime", &self.deployable_time)?;
+        state.end()
+    }
+}
+
+// A shorter timelock, in force once the current one has run out
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct QueuedUpgradeTimelock {
+    pub upgrade_timelock: Timestamp,
+    pub effective_time: Timestamp,
+}
+
+impl Serialize for QueuedUpgradeTimelock {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("QueuedUpgradeTimelock", 2)?;
+        state.serialize_field("upgradeTimelock", &U64::from(self.upgrade_timelock))?;
+        state.serialize_field("effectiveTime", &self.effective_time)?;
+        state.end()
+    }
+}
+
+#[near_bindgen]
+impl Lottery {
+    /// The timelock an upgrade staged now would get.
+    pub fn get_upgrade_timelock(&self) -> U64 {
+        match &self.queued_upgrade_timelock {
+            Some(queued) if queued.effective_time <= get_time_now() => queued.upgrade_timelock.into(),
+            _ => self.upgrade_timelock.into(),
+        }
+    }
+
+    pub fn get_queued_upgrade_timelock(&self) -> Option<&QueuedUpgradeTimelock> {
+        self.queued_upgrade_timelock.as_ref()
+    }
+
+    pub fn get_staged_upgrade(&self) -> Option<&StagedUpgrade> {
+        self.staged_upgrade.as_ref()
+    }
+
+    /// The timelock of an upgrade is fixed when it is staged, a new one applies to the next upgrades.
+    /// A longer one is in force right away, a shorter one once the current one has run out.
+    pub fn set_upgrade_timelock(&mut self, upgrade_timelock: U64) {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+        self.assert_no_multisig();
+
+        self.internal_set_upgrade_timelock(upgrade_timelock.into());
+    }
+
+    /// Announce the sha256 hash of the code to deploy, it replaces any staged upgrade.
+    pub fn stage_upgrade(&mut self, code_hash: Base58CryptoHash) {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+        self.assert_no_multisig();
+
+        self.internal_stage_upgrade(code_hash);
+    }
+
+    pub fn cancel_upgrade(&mut self) {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+
+        let staged_upgrade = self.staged_upgrade.take().unwrap_or_else(|| panic!("{}", LotteryError::NoStagedUpgrade));
+        log_upgrade_cancelled(&staged_upgrade);
+        self.record_audit(AuditAction::UpgradeCancelled { code_hash: staged_upgrade.code_hash });
+    }
+
+    /// Deploy the staged code once its timelock is over, then migrate the state to it.
+    pub fn deploy_upgrade(&mut self, code: Base64VecU8) -> Promise {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+
+        let code: Vec<u8> = code.into();
+        let staged_upgrade = self.staged_upgrade.take().unwrap_or_else(|| panic!("{}", LotteryError::NoStagedUpgrade));
+        assert!(env::sha256(&code) == staged_upgrade.code_hash, "{}", LotteryError::UpgradeCodeMismatch);
+        assert!(get_time_now() >= staged_upgrade.deployable_time, "{}", LotteryError::UpgradeTimelocked);
+
+        log_upgrade_deployed(&staged_upgrade);
+        self.record_audit(AuditAction::UpgradeDeployed { code_hash: staged_upgrade.code_hash });
+
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(b"migrate".to_vec(), vec![], 0, GAS_FOR_MIGRATE)
+    }
+}
+
+impl Lottery {
+    pub(crate) fn internal_set_upgrade_timelock(&mut self, upgrade_timelock: Timestamp) {
+        assert!(upgrade_timelock >= MIN_UPGRADE_TIMELOCK, "{}", LotteryError::UpgradeTimelockTooShort);
+        self.apply_queued_upgrade_timelock();
+
+        // An upgrade staged with a shorter timelock can't be deployed before one staged now, it
+        // replaces any queued change
+        let now = get_time_now();
+        let effective_time = if upgrade_timelock < self.upgrade_timelock { now + self.upgrade_timelock } else { now };
+        if effective_time > now {
+            self.queued_upgrade_timelock = Some(QueuedUpgradeTimelock { upgrade_timelock, effective_time });
+        }
+        else {
+            self.upgrade_timelock = upgrade_timelock;
+            self.queued_upgrade_timelock = Option::None;
+        }
+
+        log_upgrade_timelock_changed(upgrade_timelock, effective_time);
+        self.record_audit(AuditAction::UpgradeTimelockChanged { upgrade_timelock });
+    }
+
+    // Move the queued timelock in force into the current one
+    fn apply_queued_upgrade_timelock(&mut self) {
+        if matches!(&self.queued_upgrade_timelock, Some(queued) if queued.effective_time <= get_time_now()) {
+            self.upgrade_timelock = self.queued_upgrade_timelock.take().unwrap().upgrade_timelock;
+        }
+    }
+
+    pub(crate) fn internal_stage_upgrade(&mut self, code_hash: Base58CryptoHash) {
+        self.apply_queued_upgrade_timelock();
+        let staged_time = get_time_now();
+        let staged_upgrade = StagedUpgrade {
+            code_hash: code_hash.into(),
+            staged_time,
+            deployable_time: staged_time + self.upgrade_timelock,
+        };
+
+        log_upgrade_staged(&staged_upgrade);
+        self.record_audit(AuditAction::UpgradeStaged { code_hash: staged_upgrade.code_hash });
+        self.staged_upgrade = Some(staged_upgrade);
+    }
+}
@@ -1,36 +1,47 @@
 use crate::*;
 
 
-pub(crate) fn get_random_number(max: u64, mut ran_no: u64) -> u64 {
-    let mut random_number = 0; // *env::random_seed().get(0).unwrap();
-
-    // TODO: This is used for testing, try using #DEBUG
-    if random_number == 0 {
-        let mut time_value = get_time_now();
-        while time_value % 10 == 0 {
-            time_value /= 10;
-        }
-
-        if ran_no <= 0 {
-            ran_no = 1;
-        }
-
-        random_number = (time_value + ran_no) / (113 + ran_no) * (77 + ran_no) % max;
-    }
+pub(crate) fn get_random_number(max: u64, ran_no: u64) -> u64 {
+    get_random_number_from_seed(NearRuntime.seed(), max, ran_no)
+}
 
-    random_number % max + 1
+// Index range of a page, clamped to the collection length and to `MAX_PAGE_LIMIT` items
+pub(crate) fn get_page_range(len: u64, from_index: Option<u64>, limit: Option<u64>) -> std::ops::Range<u64> {
+    let from_index = std::cmp::min(from_index.unwrap_or(0), len);
+    let limit = std::cmp::min(limit.unwrap_or(DEFAULT_PAGE_LIMIT), MAX_PAGE_LIMIT);
+    from_index..std::cmp::min(from_index.saturating_add(limit), len)
 }
 
 pub(crate) fn get_time_now() -> Timestamp {
     env::block_timestamp()
 }
 
-pub(crate) fn compare_numbers(n1: &[u8; 6], n2: &[u8; 6]) -> bool {
-    for i in [0..6] {
-        if n1[i.clone()] != n2[i] { 
-            return false;
-        }
+pub(crate) fn hash_account_id(account_id: &AccountId) -> CryptoHash {
+    let mut hash = CryptoHash::default();
+    hash.copy_from_slice(&env::sha256(account_id.as_bytes()));
+    hash
+}
+
+// Whether the vector is stored under the prefix
+pub(crate) fn has_prefix<T>(vector: &Vector<T>, prefix: StorageKey) -> bool {
+    let prefix = near_sdk::IntoStorageKey::into_storage_key(prefix);
+    vector.try_to_vec().unwrap().ends_with(&prefix)
+}
+
+// Copy the elements of a vector under a new prefix and remove the old ones.
+// Returns false when the vector already uses that prefix.
+pub(crate) fn move_vector<T>(vector: &mut Vector<T>, prefix: StorageKey) -> bool {
+    let prefix = near_sdk::IntoStorageKey::into_storage_key(prefix);
+    if vector.try_to_vec().unwrap().ends_with(&prefix) {
+        return false;
     }
 
+    let mut moved_vector = Vector::new(prefix);
+    for raw_element in vector.iter_raw() {
+        moved_vector.push_raw(&raw_element);
+    }
+    vector.clear();
+    *vector = moved_vector;
+
     true
-}
\ No newline at end of file
+}
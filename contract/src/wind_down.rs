@@ -0,0 +1,152 @@
+use crate::*;
+
+const DEFAULT_REFUND_LIMIT: u64 = 100;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct WindDown {
+    pub started_time: Timestamp,
+    // Jackpot whose tickets are being refunded, cleared once all of them are
+    pub refund_jackpot_id: Option<JackpotId>,
+    // Index of the next ticket to refund
+    pub refund_index: u64,
+}
+
+impl Serialize for WindDown {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("WindDown", 3)?;
+        state.serialize_field("startedTime", &self.started_time)?;
+        state.serialize_field("refundJackpotId", &self.refund_jackpot_id)?;
+        state.serialize_field("refundIndex", &self.refund_index)?;
+        state.end()
+    }
+}
+
+// Once wound down the lottery refuses deposits, jackpots, tickets and drawings for good. The
+// players keep claiming their prizes and withdrawing their balance.
+#[near_bindgen]
+impl Lottery {
+    pub fn get_wind_down(&self) -> Option<&WindDown> {
+        self.wind_down.as_ref()
+    }
+
+    /// Stop the lottery, this can't be undone. The open jackpot is closed without winners and
+    /// its tickets are refunded by `refund_tickets`.
+    pub fn start_wind_down(&mut self) {
+        // Check account right (The caller must be the contract owner)
+        let account_id = env::predecessor_account_id();
+        assert!(account_id == self.owner_id, "{}", LotteryError::NotOwner);
+        self.assert_no_multisig();
+
+        self.internal_start_wind_down();
+    }
+
+    /// Refund the price of the next `limit` tickets of the closed jackpot to their buyers'
+    /// balance, the fee is taken back from the treasury. Anyone can call it, returns true once
+    /// every ticket has been refunded and the rest of the jackpot paid into the treasury.
+    pub fn refund_tickets(&mut self, limit: Option<u64>) -> bool {
+        let wind_down = self.wind_down.as_ref().unwrap_or_else(|| panic!("{}", LotteryError::NotWindingDown));
+        let jackpot_id = match wind_down.refund_jackpot_id {
+            Some(jackpot_id) => jackpot_id,
+            None => return true,
+        };
+        let from_index = wind_down.refund_index;
+
+        let mut jackpot = self.internal_get_jackpot(jackpot_id).unwrap();
+        let fee = jackpot.get_ticket_fee();
+        let no_of_tickets = jackpot.ticket_ids.len();
+        let to_index = std::cmp::min(from_index + limit.unwrap_or(DEFAULT_REFUND_LIMIT), no_of_tickets);
+
+        let mut ticket_ids = Vec::new();
+        for i in from_index..to_index {
+            let ticket_id = jackpot.ticket_ids.get(i).unwrap();
+            let ticket = self.tickets.get(&ticket_id).unwrap();
+
+            let mut account_info = self.get_account_info_or_default(&ticket.account_id);
+            account_info.balance += jackpot.ticket_price;
+            jackpot.locked_amount -= jackpot.ticket_price - fee;
+            self.treasury -= fee;
+            self.fund_totals.player_balance += jackpot.ticket_price;
+            self.fund_totals.locked_amount -= jackpot.ticket_price - fee;
+            self.account_infoes.insert(&ticket.account_id, &account_info);
+
+            ticket_ids.push(ticket_id);
+        }
+
+        if !ticket_ids.is_empty() {
+            log_tickets_refunded(jackpot_id, &ticket_ids, jackpot.ticket_price, fee);
+        }
+
+        // What is left is the seed and the prizes rolled over into the jackpot, nobody can win
+        // it any more so it goes to the treasury
+        let is_refunded = to_index >= no_of_tickets;
+        if is_refunded && jackpot.locked_amount > 0 {
+            let remainder = jackpot.locked_amount;
+            jackpot.locked_amount = 0;
+            self.treasury += remainder;
+            self.fund_totals.locked_amount -= remainder;
+            log_jackpot_remainder_released(jackpot_id, remainder);
+        }
+        self.update_jackpot(&jackpot);
+
+        let wind_down = self.wind_down.as_mut().unwrap();
+        if is_refunded {
+            wind_down.refund_jackpot_id = Option::None;
+        }
+        wind_down.refund_index = to_index;
+
+        is_refunded
+    }
+}
+
+impl Lottery {
+    pub(crate) fn internal_start_wind_down(&mut self) {
+        self.assert_not_winding_down();
+
+        // A jackpot is only created once the previous one is closed, so only the latest one can be open
+        let mut refund_jackpot_id = Option::None;
+        if let Some(mut jackpot) = self.get_latest_jackpot() {
+            if !matches!(jackpot.get_status(), JackpotStatus::Close) {
+                assert!(jackpot.storage_move.is_none(), "{}", LotteryError::JackpotStorageMoving);
+                // The winners found by an unfinished settlement are left in place but can't claim,
+                // every ticket is refunded
+                jackpot.settle_index = Option::None;
+                jackpot.is_refunded = true;
+                jackpot.end_time = Some(get_time_now());
+                self.update_latest_jackpot(&jackpot);
+                refund_jackpot_id = Some(jackpot.id);
+            }
+        }
+
+        let wind_down = WindDown {
+            started_time: get_time_now(),
+            refund_jackpot_id,
+            refund_index: 0,
+        };
+        log_wind_down_started(&wind_down);
+        self.record_audit(AuditAction::WindDownStarted { refund_jackpot_id });
+        self.wind_down = Some(wind_down);
+    }
+
+    /// Fees of the tickets which are refunded if the lottery winds down, the treasury keeps them.
+    pub(crate) fn get_refundable_fees(&self) -> Balance {
+        let (jackpot, refund_index) = match self.wind_down.as_ref() {
+            Some(wind_down) => match wind_down.refund_jackpot_id {
+                Some(jackpot_id) => (self.internal_get_jackpot(jackpot_id).unwrap(), wind_down.refund_index),
+                None => return 0,
+            },
+            None => match self.get_latest_jackpot() {
+                Some(jackpot) if !matches!(jackpot.get_status(), JackpotStatus::Close) => (jackpot, 0),
+                _ => return 0,
+            },
+        };
+
+        jackpot.get_ticket_fee() * u128::from(jackpot.ticket_ids.len() - refund_index)
+    }
+
+    pub(crate) fn assert_not_winding_down(&self) {
+        assert!(self.wind_down.is_none(), "{}", LotteryError::WindingDown);
+    }
+}
@@ -0,0 +1,239 @@
+/*
+ * Draw verifier for the lottery contract.
+ *
+ * It takes the exported state of a jackpot and re-derives every drawing result from its
+ * recorded seed with the same lottery core as the contract, then checks the winner set.
+ * The export is a JSON object with the `get_jackpot` view of the jackpot, every page of its
+ * ticket ids, win tickets and drawing results, and its tickets:
+ *
+ *   { "jackpot": <get_jackpot>, "ticketIds": [<get_jackpot_ticket_ids>, ...],
+ *     "winTickets": [<get_jackpot_win_tickets>, ...], "drawedResults": [<get_jackpot_drawed_results>, ...],
+ *     "tickets": [<get_ticket>, ...] }
+ *
+ * Usage:
+ *   lottery-verifier [FILE]   Read the export from FILE, or stdin when omitted
+ *
+ * The exit code is 1 when any mismatch is found.
+ *
+ */
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{self, Read};
+use std::process;
+
+use lottery_core::*;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JackpotExport {
+    jackpot: Jackpot,
+    ticket_ids: Vec<u64>,
+    win_tickets: Vec<WinTicket>,
+    drawed_results: Vec<DrawingResult>,
+    tickets: Vec<Ticket>,
+}
+
+#[derive(Deserialize)]
+struct Jackpot {
+    id: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WinTicket {
+    ticket_id: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DrawingResult {
+    drawed_numbers: [u8; 6],
+    seed: String,
+    no_of_tickets: u64,
+    lucky_ticket_index: Option<u64>,
+    // Rules the result was drawn with
+    version: u8,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Ticket {
+    id: u64,
+    picked_numbers: [u8; 6],
+}
+
+fn main() {
+    let path = std::env::args().nth(1);
+    match read_export(path.as_deref()).and_then(|export| verify(&export)) {
+        Ok(mismatches) => {
+            if !mismatches.is_empty() {
+                for mismatch in &mismatches {
+                    println!("MISMATCH {}", mismatch);
+                }
+                process::exit(1);
+            }
+            println!("OK");
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        },
+    }
+}
+
+fn read_export(path: Option<&str>) -> Result<JackpotExport, String> {
+    let mut input = String::new();
+    match path {
+        None | Some("-") => io::stdin().read_to_string(&mut input),
+        Some(path) => File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut input))
+            .map_err(|e| io::Error::new(e.kind(), format!("Cannot open {}: {}", path, e))),
+    }
+    .map_err(|e| e.to_string())?;
+
+    serde_json::from_str(&input).map_err(|e| format!("Invalid jackpot export: {}", e))
+}
+
+/// Re-derive every drawing result and winner set of the jackpot, returns the mismatches found.
+fn verify(export: &JackpotExport) -> Result<Vec<String>, String> {
+    let jackpot = &export.jackpot;
+    let tickets: HashMap<u64, [u8; 6]> = export.tickets.iter()
+        .map(|ticket| (ticket.id, ticket.picked_numbers))
+        .collect();
+
+    let get_picked_numbers = |ticket_id: u64| tickets.get(&ticket_id)
+        .copied()
+        .ok_or_else(|| format!("Ticket {} of jackpot {} is missing from the export.", ticket_id, jackpot.id));
+
+    let mut mismatches = Vec::new();
+    for (i, result) in export.drawed_results.iter().enumerate() {
+        let seed: u64 = result.seed.parse().map_err(|_| format!("Invalid seed {}.", result.seed))?;
+        if result.no_of_tickets > export.ticket_ids.len() as u64 {
+            mismatches.push(format!("result #{}: drawn over {} tickets but the jackpot has {}", i + 1, result.no_of_tickets, export.ticket_ids.len()));
+            continue;
+        }
+
+        // A forced result takes the numbers of a ticket picked from the seed
+        let expected_numbers = match result.lucky_ticket_index {
+            None if result.version < 2 => draw_legacy_numbers(seed),
+            None => draw_numbers(seed),
+            Some(lucky_ticket_index) if result.no_of_tickets == 0 => {
+                mismatches.push(format!("result #{}: lucky ticket index is {} but no ticket was drawn", i + 1, lucky_ticket_index));
+                continue;
+            },
+            Some(lucky_ticket_index) => {
+                let expected_index = pick_lucky_ticket_index(seed, result.no_of_tickets);
+                if lucky_ticket_index != expected_index {
+                    mismatches.push(format!("result #{}: lucky ticket index is {} but the seed gives {}", i + 1, lucky_ticket_index, expected_index));
+                }
+                get_picked_numbers(export.ticket_ids[expected_index as usize])?
+            },
+        };
+
+        if expected_numbers != result.drawed_numbers {
+            mismatches.push(format!("result #{}: drawn numbers are {:?} but the seed gives {:?}", i + 1, result.drawed_numbers, expected_numbers));
+        }
+
+        // Only the tickets bought before the drawing can win it
+        let drawed_mask = numbers_to_bitmask(&result.drawed_numbers);
+        let mut winners = BTreeSet::new();
+        for ticket_id in &export.ticket_ids[..result.no_of_tickets as usize] {
+            if count_matches(numbers_to_bitmask(&get_picked_numbers(*ticket_id)?), drawed_mask) == 6 {
+                winners.insert(*ticket_id);
+            }
+        }
+
+        // The jackpot closes on the first result with winners, so it must be the last one
+        let is_last_result = i + 1 == export.drawed_results.len();
+        let expected_winners: BTreeSet<u64> = if is_last_result {
+            export.win_tickets.iter().map(|win_ticket| win_ticket.ticket_id).collect()
+        }
+        else {
+            BTreeSet::new()
+        };
+
+        if winners != expected_winners {
+            mismatches.push(format!("result #{}: winning tickets are {:?} but the recorded ones are {:?}", i + 1, winners, expected_winners));
+        }
+    }
+
+    Ok(mismatches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 1_640_995_200_123_456_789;
+
+    fn export(drawed_numbers: [u8; 6], lucky_ticket_index: Option<u64>, win_ticket_ids: Vec<u64>) -> JackpotExport {
+        JackpotExport {
+            jackpot: Jackpot { id: 1 },
+            ticket_ids: vec![1, 2],
+            win_tickets: win_ticket_ids.into_iter().map(|ticket_id| WinTicket { ticket_id }).collect(),
+            drawed_results: vec![DrawingResult {
+                drawed_numbers,
+                seed: SEED.to_string(),
+                no_of_tickets: 2,
+                lucky_ticket_index,
+                version: DRAWING_VERSION,
+            }],
+            tickets: vec![
+                Ticket { id: 1, picked_numbers: [1, 2, 3, 4, 5, 6] },
+                Ticket { id: 2, picked_numbers: draw_numbers(SEED) },
+            ],
+        }
+    }
+
+    #[test]
+    fn verify_drawing_result() {
+        assert!(verify(&export(draw_numbers(SEED), None, vec![2])).unwrap().is_empty());
+
+        // Tampered numbers and a hidden winner are both reported
+        let mismatches = verify(&export([1, 2, 3, 4, 5, 6], None, vec![2])).unwrap();
+        assert_eq!(2, mismatches.len());
+    }
+
+    #[test]
+    fn verify_legacy_result() {
+        let mut export = export(draw_legacy_numbers(SEED), None, vec![]);
+        export.tickets[1].picked_numbers = draw_legacy_numbers(SEED);
+        export.win_tickets.push(WinTicket { ticket_id: 2 });
+        export.drawed_results[0].version = 1;
+        assert!(verify(&export).unwrap().is_empty());
+
+        // The numbers of the current rules don't pass for a legacy result
+        export.drawed_results[0].drawed_numbers = draw_numbers(SEED);
+        assert!(!verify(&export).unwrap().is_empty());
+    }
+
+    #[test]
+    fn verify_forced_result() {
+        let lucky_ticket_index = pick_lucky_ticket_index(SEED, 2);
+        let lucky_ticket_id = lucky_ticket_index + 1;
+        let drawed_numbers = export(draw_numbers(SEED), None, vec![]).tickets[lucky_ticket_index as usize].picked_numbers;
+
+        assert!(verify(&export(drawed_numbers, Some(lucky_ticket_index), vec![lucky_ticket_id])).unwrap().is_empty());
+        assert!(!verify(&export(drawed_numbers, Some(1 - lucky_ticket_index), vec![lucky_ticket_id])).unwrap().is_empty());
+
+        // A lucky ticket drawn among no ticket is reported instead of dividing by zero
+        let mut export = export(drawed_numbers, Some(0), vec![]);
+        export.drawed_results[0].no_of_tickets = 0;
+        assert_eq!(1, verify(&export).unwrap().len());
+    }
+
+    #[test]
+    fn parse_export() {
+        let export: JackpotExport = serde_json::from_str(r#"{
+            "jackpot": { "id": 1, "ticketPrice": "1", "noOfTickets": 1 },
+            "ticketIds": [1],
+            "winTickets": [],
+            "drawedResults": [{ "drawedNumbers": [1, 2, 3, 4, 5, 6], "drawedMask": "126", "createdTime": 11, "seed": "11", "noOfTickets": 1, "luckyTicketIndex": null, "version": 2 }],
+            "tickets": [{ "id": 1, "accountId": "bob_near", "pickedNumbers": [1, 2, 3, 4, 5, 7] }]
+        }"#).unwrap();
+
+        assert_eq!(1, export.drawed_results.len());
+        assert_eq!(None, export.drawed_results[0].lucky_ticket_index);
+    }
+}
@@ -9,6 +9,8 @@ const nearAPI = require('near-api-js');
 const { KeyPair, Account, utils: { format: { parseNearAmount, formatNearAmount }} } = nearAPI;
 
 const GAS = "200000000000000";
+// Storage deposit attached when registering, it pays for the account and its first tickets
+const STORAGE_DEPOSIT = "0.1";
 
 export default function App() {
   // use React Hooks to store greeting in component state
@@ -54,9 +56,15 @@ export default function App() {
   }
 
   function getJackpotListFromContract() {
-    window.contract.get_jackpots({})
-      .then(result => {
+    window.contract.get_jackpot_summaries({})
+      .then(async result => {
         console.log('Get jackpot list from contract: ', result)
+        // The summaries only count the results and the winners, they are read per jackpot
+        for (const jackpot of result) {
+          jackpot.drawedResults = await window.contract.get_jackpot_drawed_results({ jackpot_id: jackpot.id })
+          jackpot.winTicketIds = jackpot.noOfWinTickets == 0 ? [] :
+            (await window.contract.get_jackpot_win_tickets({ jackpot_id: jackpot.id })).map(winTicket => winTicket.ticketId)
+        }
         setJackpots(result)
 
         getAccountTicketsFromContract(result)
@@ -72,14 +80,13 @@ export default function App() {
   }
 
   function getAccountTicketsFromContract(jackpots) {
-    window.contract.get_account_tickets({account_id: window.accountId})
+    window.contract.get_account_tickets_paged({account_id: window.accountId})
       .then(result => {
         console.log('Get Account Tickets from contract: ', result)
         result.map(item => {
-          let jackpot = jackpots.find(j => j.startTime <= item.createdTime && (!j.endTime || item.createdTime <= j.endTime))
+          let jackpot = jackpots.find(j => j.id == item.jackpotId)
           console.log('Jackpots', jackpots , 'Jackpot', jackpot)
-          item.jackpotId = jackpot.id
-          item.result = jackpot.winTicketIds.includes(item.id) ? 'Won' : jackpot.status == 'Open' ? 'Waiting' : 'Loss'
+          item.result = !jackpot ? '' : jackpot.winTicketIds.includes(item.id) ? 'Won' : jackpot.status == 'Open' ? 'Waiting' : 'Loss'
         })
         console.log('Get Account Tickets from contract: ', result)
         setAccountTickets(result)
@@ -144,6 +151,12 @@ export default function App() {
           fieldset.disabled = true
 
           try {
+            // the account must be registered first, this goes through the wallet and the deposit is submitted again afterwards
+            const storageBalance = await window.contract.storage_balance_of({ account_id: window.accountId })
+            if (!storageBalance) {
+              await window.contract.storage_deposit({}, GAS, parseNearAmount(STORAGE_DEPOSIT))
+            }
+
             // make an update call to the smart contract
             await window.contract.deposit({}, GAS, parseNearAmount(depositAmount))
           } catch (e) {
@@ -405,8 +418,12 @@ export default function App() {
                   try {
                     // make an update call to the smart contract
                     console.log('Jackpot is drawing...')
-                    let result = await window.contract.draw_jackpot({force_win: false}, GAS)
-                    console.log('Jackpot drawn with result: ', result)
+                    await window.contract.draw_jackpot({}, GAS)
+                    let settled = false
+                    while (!settled) {
+                      settled = await window.contract.settle_jackpot({}, GAS)
+                    }
+                    console.log('Jackpot drawn and settled.')
                   } catch (e) {
                     alert('Something went wrong!')
                     throw e
@@ -417,25 +434,6 @@ export default function App() {
               >
                 Draw Jackpot
               </button>
-              <button
-                style={{ borderRadius: '5px', marginRight: '10px' }}
-                disabled={!hasOpenJackpot()}
-                onClick={async () => {
-                  try {
-                    // make an update call to the smart contract
-                    console.log('Jackpot is drawing...')
-                    let result = await window.contract.draw_jackpot({force_win: true}, GAS)
-                    console.log('Jackpot drawn with result: ', result)
-                  } catch (e) {
-                    alert('Something went wrong!')
-                    throw e
-                  } finally {
-
-                  }
-                }}
-              >
-                Draw to Win
-              </button>
             </div>
               
           </>
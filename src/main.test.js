@@ -4,7 +4,7 @@ beforeAll(async function () {
   window.accountId = nearConfig.contractName
   window.contract = await near.loadContract(nearConfig.contractName, {
     viewMethods: ['get_owner_id', 'get_account_balance'],
-    changeMethods: ['new', 'deposit', 'create_jackpot', 'get_jackpots'],
+    changeMethods: ['new', 'storage_deposit', 'deposit', 'create_jackpot'],
     sender: window.accountId
   })
 
@@ -53,6 +53,7 @@ test('get_account_balance', async () => {
 
 test('deposit', async () => {
   const depositAmount = parseNearAmount('1');
+  await window.contract.storage_deposit({}, GAS, parseNearAmount('0.1'))
   await window.contract.deposit({}, GAS, depositAmount)
   const balance = await window.contract.get_account_balance({ account_id: window.accountId })
   expect(depositAmount).toEqual(balance)
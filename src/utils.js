@@ -19,9 +19,9 @@ export async function initContract() {
   // Initializing our contract APIs by contract name and configuration
   window.contract = await new Contract(window.walletConnection.account(), nearConfig.contractName, {
     // View methods are read only. They don't modify the state, but usually return some value.
-    viewMethods: ['get_owner_id', 'get_account_balance', 'get_jackpots', 'get_account_info_or_default', 'get_account_tickets', 'has_initialized'],
+    viewMethods: ['get_owner_id', 'get_account_balance', 'get_account_info_or_default', 'has_initialized', 'get_jackpot', 'get_jackpot_summaries', 'get_jackpot_ticket_ids', 'get_jackpot_win_tickets', 'get_jackpot_drawed_results', 'get_account_tickets_paged', 'get_account_ids', 'get_ticket', 'get_tickets', 'get_staged_upgrade', 'get_upgrade_timelock', 'get_queued_upgrade_timelock', 'storage_balance_of', 'storage_balance_bounds', 'has_role', 'get_roles', 'get_role_members', 'get_proposed_owner_id', 'is_paused', 'get_wind_down', 'get_multisig', 'get_proposal', 'get_proposals', 'get_config', 'get_queued_config', 'get_audit_log', 'get_audit_log_length', 'get_treasury', 'get_balance_sheet'],
     // Change methods can modify the state. But you don't receive the returned value when called.
-    changeMethods: ['new', 'set_owner_id', 'create_jackpot', 'deposit', 'withdraw', 'buy_ticket', 'draw_jackpot'],
+    changeMethods: ['new', 'propose_owner', 'cancel_ownership_proposal', 'accept_ownership', 'create_jackpot', 'deposit', 'withdraw', 'buy_ticket', 'draw_jackpot', 'settle_jackpot', 'claim_prize', 'storage_deposit', 'storage_withdraw', 'storage_unregister', 'grant_role', 'revoke_role', 'set_paused', 'set_jackpot_sales_paused', 'set_jackpot_drawing_paused', 'start_wind_down', 'refund_tickets', 'set_multisig', 'propose', 'approve', 'remove_proposal', 'schedule_config', 'cancel_queued_config', 'withdraw_treasury'],
   })
 
   await initializeContract();